@@ -0,0 +1,296 @@
+//! `#[derive(Metric)]` for metrics whose whole state is the last sampled
+//! value.
+//!
+//! ```ignore
+//! #[derive(Default, Metric)]
+//! #[metric(name = "xkblayout", sample = sample, icon = layout, reset_on_error)]
+//! pub struct XkbLayoutMetric {
+//!     #[metric(state)]
+//!     layout: Cell<Option<Locale>>,
+//!     #[metric(icons)]
+//!     icons: Icons,
+//! }
+//!
+//! impl XkbLayoutMetric {
+//!     async fn sample(&self) -> Result<Locale, CommonError> { ... }
+//! }
+//! ```
+//!
+//! The state field is a `Cell<Option<T>>`, marked with `#[metric(state)]` if
+//! the struct has more than one field. `sample` is an async method returning
+//! `Result<T, impl Error>`, or `Result<Option<T>, impl Error>` with `optional`;
+//! its value is stored on success. With `reset_on_error` a failed sample
+//! clears the state, otherwise the last value is kept. The metric displays
+//! nothing while the state is empty, and the `format` template otherwise, with
+//! the value named after the state field. `name` defaults to the name of the
+//! struct and `format` to the bare value. The value is also the
+//! `Metric::value`, as text, and a field for `barstatus::template` format
+//! templates, named after the state field too.
+//!
+//! With `icon = name` the icon `name` of the `barstatus::icons::Icons` field
+//! marked `#[metric(icons)]` is available as `{icon}`, both in `format` and in
+//! templates, and `format` defaults to the icon and the value.
+//!
+//! `tooltip`, `level` and `value` name methods of the struct that replace
+//! those `Metric` methods, the last one returning a `barstatus::Value` or an
+//! `Option` of one. A `barstatus::history::History` field marked
+//! `#[metric(history)]` gets the number of the value after every successful
+//! sample.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Ident, LitStr, Result};
+
+#[proc_macro_derive(Metric, attributes(metric))]
+pub fn derive_metric(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+#[derive(Default)]
+struct Options {
+    name: Option<LitStr>,
+    sample: Option<Ident>,
+    format: Option<LitStr>,
+    icon: Option<Ident>,
+    tooltip: Option<Ident>,
+    level: Option<Ident>,
+    value: Option<Ident>,
+    optional: bool,
+    reset_on_error: bool,
+}
+
+fn options(input: &DeriveInput) -> Result<Options> {
+    let mut options = Options::default();
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("metric")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                options.name = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("sample") {
+                options.sample = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("format") {
+                options.format = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("icon") {
+                options.icon = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("tooltip") {
+                options.tooltip = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("level") {
+                options.level = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("value") {
+                options.value = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("optional") {
+                options.optional = true;
+            } else if meta.path.is_ident("reset_on_error") {
+                options.reset_on_error = true;
+            } else {
+                return Err(meta.error(
+                    "expected `name`, `sample`, `format`, `icon`, `tooltip`, `level`, `value`, \
+                     `optional` or `reset_on_error`",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+/// The fields marked with `#[metric(state)]`, `#[metric(icons)]` and
+/// `#[metric(history)]`.
+struct Marked<'a> {
+    state: &'a Ident,
+    icons: Option<&'a Ident>,
+    history: Option<&'a Ident>,
+}
+
+/// The marked fields. The state defaults to the only unmarked field.
+fn fields(input: &DeriveInput) -> Result<Marked<'_>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(input, "`Metric` can only be derived for structs"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(&data.fields, "expected named fields"));
+    };
+
+    let (mut state, mut icons, mut history, mut unmarked) = (None, None, None, Vec::new());
+    for field in &fields.named {
+        let ident = field.ident.as_ref().expect("Named fields have names");
+        let mut marked = false;
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("metric")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("state") {
+                    state = Some(ident);
+                } else if meta.path.is_ident("icons") {
+                    icons = Some(ident);
+                } else if meta.path.is_ident("history") {
+                    history = Some(ident);
+                } else {
+                    return Err(meta.error("expected `state`, `icons` or `history`"));
+                }
+                marked = true;
+                Ok(())
+            })?;
+        }
+        if !marked {
+            unmarked.push(ident);
+        }
+    }
+
+    match (state, unmarked.as_slice()) {
+        (Some(state), _) | (None, &[state]) => Ok(Marked { state, icons, history }),
+        (None, _) => {
+            Err(Error::new_spanned(fields, "mark the state field with `#[metric(state)]`"))
+        }
+    }
+}
+
+/// The `value` method, and the `tooltip`, `level` and `history` methods if the
+/// options or a `#[metric(history)]` field replace them.
+fn overrides(
+    options: &Options,
+    state: &Ident,
+    history: Option<&Ident>,
+) -> proc_macro2::TokenStream {
+    let value = match &options.value {
+        Some(value) => quote!(::std::convert::Into::into(self.#value())),
+        None => quote! {
+            let state = self.#state.take()?;
+            let value = ::barstatus::Value::text(&state);
+            self.#state.set(Some(state));
+            value
+        },
+    };
+    let tooltip = options.tooltip.as_ref().map(|tooltip| {
+        quote! {
+            fn tooltip(&self) -> impl ::std::fmt::Display {
+                self.#tooltip()
+            }
+        }
+    });
+    let level = options.level.as_ref().map(|level| {
+        quote! {
+            fn level(&self) -> ::barstatus::Level {
+                self.#level()
+            }
+        }
+    });
+    let history = history.map(|history| {
+        quote! {
+            fn history(&self) -> Option<&::barstatus::history::History> {
+                Some(&self.#history)
+            }
+        }
+    });
+    quote! {
+        fn value(&self) -> Option<::barstatus::Value> {
+            #value
+        }
+
+        #tooltip
+        #level
+        #history
+    }
+}
+
+fn expand(input: &DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let options = options(input)?;
+    let Marked { state, icons, history } = fields(input)?;
+    let overrides = overrides(&options, state, history);
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let name = options.name.unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span()));
+    let Some(sample) = options.sample else {
+        return Err(Error::new(Span::call_site(), "missing `#[metric(sample = method)]`"));
+    };
+    let icon = match (&options.icon, icons) {
+        (Some(icon), Some(icons)) => Some(quote!(&self.#icons.#icon)),
+        (Some(icon), None) => {
+            return Err(Error::new_spanned(icon, "mark the `Icons` field with `#[metric(icons)]`"));
+        }
+        (None, _) => None,
+    };
+    let format = options.format.unwrap_or_else(|| match icon {
+        Some(_) => LitStr::new(&format!("{{icon}} {{{state}}}"), state.span()),
+        None => LitStr::new(&format!("{{{state}}}"), state.span()),
+    });
+    let format_icon = icon.as_ref().map(|icon| quote!(, icon = #icon));
+    let on_error = options.reset_on_error.then(|| quote!(self.#state.set(None);));
+    let stored = if options.optional { quote!(value) } else { quote!(Some(value)) };
+    let push = history.map(|history| {
+        quote! {
+            let value = ::barstatus::Metric::value(self);
+            if let Some(number) = value.as_ref().and_then(::barstatus::Value::number) {
+                self.#history.push(number);
+            }
+        }
+    });
+    let field = LitStr::new(&state.to_string(), state.span());
+    let (field_names, icon_match) = match &icon {
+        Some(icon) => (quote!(&["icon", #field]), quote!("icon" => Some(out.write_str(#icon)),)),
+        None => (quote!(&[#field]), quote!()),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::barstatus::Metric for #ident #ty_generics #where_clause {
+            fn name(&self) -> &'static str {
+                #name
+            }
+
+            fn display(&self) -> impl ::std::fmt::Display {
+                self
+            }
+
+            fn update(
+                &self,
+            ) -> impl ::std::future::Future<Output = Result<(), impl ::std::error::Error>> + '_ {
+                async move {
+                    match self.#sample().await {
+                        Ok(value) => {
+                            self.#state.set(#stored);
+                            #push
+                            Ok(())
+                        }
+                        Err(err) => {
+                            #on_error
+                            Err(err)
+                        }
+                    }
+                }
+            }
+
+            #overrides
+
+            fn fields(&self) -> &'static [&'static str] {
+                #field_names
+            }
+
+            fn write_field(
+                &self,
+                name: &str,
+                out: &mut dyn ::std::fmt::Write,
+            ) -> Option<::std::fmt::Result> {
+                let state = self.#state.take()?;
+                let result = match name {
+                    #icon_match
+                    #field => Some(write!(out, "{state}")),
+                    _ => None,
+                };
+                self.#state.set(Some(state));
+                result
+            }
+
+        }
+
+        impl #impl_generics ::std::fmt::Display for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                let Some(state) = self.#state.take() else {
+                    return Ok(());
+                };
+                let result = write!(f, #format, #state = state #format_icon);
+                self.#state.set(Some(state));
+                result
+            }
+        }
+    })
+}
@@ -0,0 +1,288 @@
+//! Rules that fire a desktop notification when a metric crosses a threshold.
+
+use std::{
+    cell::Cell,
+    fmt::{self, Display, Formatter, Write as _},
+    rc::Rc,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    notification::{Notification, Urgency},
+    Level, Metric, Value,
+};
+
+/// Default distance a value has to move back past the threshold before the
+/// rule can fire again, e.g. percentage points.
+pub const DEFAULT_HYSTERESIS: f64 = 5.;
+
+/// Default time a [`Condition::Level`] rule has to stay below its level before
+/// it can fire again, as levels have no hysteresis.
+pub const DEFAULT_REARM: Duration = Duration::from_secs(60);
+
+/// How long the notification server gets to answer.
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Condition {
+    /// The [`Value::number`] of the metric is below the value.
+    Below(f64),
+    /// The [`Value::number`] of the metric is above the value.
+    Above(f64),
+    /// [`Metric::level`] is at least this level.
+    Level(Level),
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Below(value) => write!(f, "below {value}"),
+            Condition::Above(value) => write!(f, "above {value}"),
+            Condition::Level(level) => write!(f, "{}", level.as_str()),
+        }
+    }
+}
+
+/// A field of the metric that must have a given value for a rule to fire, e.g.
+/// `state=discharging` for the battery.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct When {
+    pub field: String,
+    pub value: String,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Expected `field=value`")]
+pub struct ParseWhenError;
+
+impl FromStr for When {
+    type Err = ParseWhenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (field, value) = s.split_once('=').ok_or(ParseWhenError)?;
+        let field = field.trim();
+        if field.is_empty() {
+            return Err(ParseWhenError);
+        }
+        Ok(Self { field: field.into(), value: value.trim().into() })
+    }
+}
+
+impl When {
+    fn holds(&self, metric: &impl Metric) -> bool {
+        let mut value = String::new();
+        metric.write_field(&self.field, &mut value) == Some(Ok(())) && value == self.value
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub condition: Condition,
+    /// How long the condition has to hold before the rule fires.
+    pub sustain: Duration,
+    /// See [`DEFAULT_HYSTERESIS`]. Unused for [`Condition::Level`].
+    pub hysteresis: f64,
+    /// How long the rule has to stay re-armed, far enough from the condition,
+    /// before it can fire again. [`DEFAULT_REARM`] for levels, zero otherwise.
+    pub rearm: Duration,
+    /// The rule neither fires nor re-arms while this does not hold.
+    pub when: Option<When>,
+}
+
+impl Rule {
+    #[must_use]
+    pub fn new(condition: Condition) -> Self {
+        let rearm = match condition {
+            Condition::Level(_) => DEFAULT_REARM,
+            Condition::Below(_) | Condition::Above(_) => Duration::ZERO,
+        };
+        Self {
+            condition,
+            sustain: Duration::ZERO,
+            hysteresis: DEFAULT_HYSTERESIS,
+            rearm,
+            when: None,
+        }
+    }
+
+    #[must_use]
+    pub fn sustained_for(self, sustain: Duration) -> Self {
+        Self { sustain, ..self }
+    }
+
+    #[must_use]
+    pub fn with_hysteresis(self, hysteresis: f64) -> Self {
+        Self { hysteresis, ..self }
+    }
+
+    #[must_use]
+    pub fn rearmed_after(self, rearm: Duration) -> Self {
+        Self { rearm, ..self }
+    }
+
+    /// Only fires while `when` holds.
+    #[must_use]
+    pub fn when(self, when: When) -> Self {
+        Self { when: Some(when), ..self }
+    }
+
+    /// `Some(true)` if the condition holds, `Some(false)` if the metric is
+    /// far enough from it to re-arm the rule, `None` in between, if the metric
+    /// has no numeric value or if `when` does not hold.
+    fn state(&self, metric: &impl Metric) -> Option<bool> {
+        if self.when.as_ref().is_some_and(|when| !when.holds(metric)) {
+            return None;
+        }
+        let number = metric.value().as_ref().and_then(Value::number);
+        match self.condition {
+            Condition::Below(threshold) => number.and_then(|value| {
+                if value < threshold {
+                    Some(true)
+                } else {
+                    (value >= threshold + self.hysteresis).then_some(false)
+                }
+            }),
+            Condition::Above(threshold) => number.and_then(|value| {
+                if value > threshold {
+                    Some(true)
+                } else {
+                    (value <= threshold - self.hysteresis).then_some(false)
+                }
+            }),
+            Condition::Level(level) => Some(metric.level() >= level),
+        }
+    }
+
+    fn urgency(&self) -> Urgency {
+        match self.condition {
+            Condition::Level(Level::Critical) => Urgency::Critical,
+            _ => Urgency::Normal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Alert {
+    name: &'static str,
+    rule: Rule,
+    /// When the condition started to hold, if it does.
+    since: Cell<Option<Instant>>,
+    /// When the rule started to be re-armed, if it is.
+    rearmed: Cell<Option<Instant>>,
+    fired: Cell<bool>,
+    /// Whether `when` was checked against the fields of the metric.
+    checked: Cell<bool>,
+    /// Id of the last notification, so a new one replaces it. Set by the task
+    /// that sends it.
+    notification: Rc<Cell<u32>>,
+}
+
+impl Alert {
+    /// Whether the rule fires with the current state of `metric`.
+    fn check(&self, metric: &impl Metric, now: Instant) -> bool {
+        let state = self.rule.state(metric);
+        // Both only count while uninterrupted.
+        if state != Some(true) {
+            self.since.set(None);
+        }
+        if state != Some(false) {
+            self.rearmed.set(None);
+        }
+        match state {
+            Some(true) if !self.fired.get() => {
+                let since = self.since.get().unwrap_or(now);
+                self.since.set(Some(since));
+                let fire = now.duration_since(since) >= self.rule.sustain;
+                self.fired.set(fire);
+                fire
+            }
+            Some(false) => {
+                let rearmed = self.rearmed.get().unwrap_or(now);
+                self.rearmed.set(Some(rearmed));
+                if now.duration_since(rearmed) >= self.rule.rearm {
+                    self.fired.set(false);
+                }
+                false
+            }
+            Some(true) | None => false,
+        }
+    }
+
+    /// Logs a `when` on a field `metric` does not have, once, as the rule can
+    /// then never fire.
+    fn check_fields(&self, metric: &impl Metric) {
+        if self.checked.replace(true) {
+            return;
+        }
+        if let Some(when) = self.rule.when.as_ref() {
+            if !metric.fields().contains(&when.field.as_str()) {
+                log::error!(
+                    "The alerts of {} depend on the unknown field `{}`, expected one of {}",
+                    self.name,
+                    when.field,
+                    metric.fields().join(", ")
+                );
+            }
+        }
+    }
+}
+
+/// Alert rules of every metric.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Alerts(Vec<Alert>);
+
+impl Alerts {
+    pub fn push(&mut self, name: &'static str, rule: Rule) {
+        self.0.push(Alert {
+            name,
+            rule,
+            since: Cell::new(None),
+            rearmed: Cell::new(None),
+            fired: Cell::new(false),
+            checked: Cell::new(false),
+            notification: Rc::new(Cell::new(0)),
+        });
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Checks the rules of `metric` after it was updated, and sends a
+    /// notification for each one that fires. The notifications are sent by
+    /// tasks of the current [`LocalSet`](tokio::task::LocalSet), so that a slow
+    /// notification server does not hold up the updates. Errors are only
+    /// logged, a missing notification server should not stop the bar.
+    pub fn check(&self, metric: &impl Metric) {
+        let now = Instant::now();
+        for alert in self.0.iter().filter(|alert| alert.name == metric.name()) {
+            alert.check_fields(metric);
+            if !alert.check(metric, now) {
+                continue;
+            }
+
+            let mut summary = heapless::String::<128>::new();
+            let mut body = heapless::String::<256>::new();
+            // Truncated text is still worth sending.
+            _ = write!(summary, "{} {}", alert.name, alert.rule.condition);
+            if let Some(value) = metric.value() {
+                _ = write!(summary, " ({value})");
+            }
+            _ = write!(body, "{}", metric.tooltip());
+
+            let urgency = alert.rule.urgency();
+            let id = Rc::clone(&alert.notification);
+            tokio::task::spawn_local(async move {
+                let notification =
+                    Notification { summary: &summary, body: &body, urgency, replaces: id.get() };
+                match tokio::time::timeout(SEND_TIMEOUT, notification.send()).await {
+                    Ok(Ok(sent)) => id.set(sent),
+                    Ok(Err(err)) => log::warn!("Error sending a notification: {err}"),
+                    Err(_) => log::warn!("The notification server did not answer in time"),
+                }
+            });
+        }
+    }
+}
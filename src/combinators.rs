@@ -0,0 +1,360 @@
+//! Wrappers that change how a [`Metric`] is updated or shown. They are
+//! metrics themselves, so they nest and go anywhere a metric does.
+
+use std::{
+    cell::Cell,
+    error::Error,
+    fmt::{self, Display, Formatter, Write as _},
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use crate::{history::History, Level, Metric, Value};
+
+/// Longest text [`Map`] can post-process, in bytes.
+pub const MAP_CAPACITY: usize = 256;
+
+/// Implements the listed [`Metric`] methods by calling them on `self.$field`,
+/// for wrappers that only change some of them.
+macro_rules! forward_metric {
+    ($field:ident: $($method:ident),+ $(,)?) => {
+        $($crate::combinators::forward_metric!(@$method $field);)+
+    };
+    (@name $field:ident) => {
+        fn name(&self) -> &'static str {
+            self.$field.name()
+        }
+    };
+    (@display $field:ident) => {
+        fn display(&self) -> impl ::std::fmt::Display {
+            self.$field.display()
+        }
+    };
+    (@update $field:ident) => {
+        fn update(
+            &self,
+        ) -> impl ::std::future::Future<Output = Result<(), impl ::std::error::Error>> + '_ {
+            self.$field.update()
+        }
+    };
+    (@tooltip $field:ident) => {
+        fn tooltip(&self) -> impl ::std::fmt::Display {
+            self.$field.tooltip()
+        }
+    };
+    (@percentage $field:ident) => {
+        fn percentage(&self) -> Option<u8> {
+            self.$field.percentage()
+        }
+    };
+    (@level $field:ident) => {
+        fn level(&self) -> $crate::Level {
+            self.$field.level()
+        }
+    };
+    (@frame_period $field:ident) => {
+        fn frame_period(&self) -> Option<::std::time::Duration> {
+            self.$field.frame_period()
+        }
+    };
+    (@animation_frames $field:ident) => {
+        fn animation_frames(&self) -> Option<(char, char)> {
+            self.$field.animation_frames()
+        }
+    };
+    (@history $field:ident) => {
+        fn history(&self) -> Option<&$crate::history::History> {
+            self.$field.history()
+        }
+    };
+    (@value $field:ident) => {
+        fn value(&self) -> Option<$crate::Value> {
+            self.$field.value()
+        }
+    };
+    (@fields $field:ident) => {
+        fn fields(&self) -> &'static [&'static str] {
+            self.$field.fields()
+        }
+    };
+    (@write_field $field:ident) => {
+        fn write_field(
+            &self,
+            name: &str,
+            out: &mut dyn ::std::fmt::Write,
+        ) -> Option<::std::fmt::Result> {
+            self.$field.write_field(name, out)
+        }
+    };
+    (@changed $field:ident) => {
+        fn changed(&self) -> impl ::std::future::Future<Output = ()> + '_ {
+            self.$field.changed()
+        }
+    };
+}
+pub(crate) use forward_metric;
+
+/// One of two values, displayed as whichever it holds.
+#[derive(Debug)]
+pub(crate) enum Either<A, B> {
+    Left(A),
+    Right(B),
+}
+
+impl<A: Display, B: Display> Display for Either<A, B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Either::Left(a) => a.fmt(f),
+            Either::Right(b) => b.fmt(f),
+        }
+    }
+}
+
+/// Shows the metric only while `predicate` holds for it.
+#[derive(Debug, Clone)]
+pub struct Hidden<M, F> {
+    metric: M,
+    predicate: F,
+}
+
+impl<M: Metric, F: Fn(&M) -> bool> Metric for Hidden<M, F> {
+    forward_metric!(
+        metric: name,
+        update,
+        tooltip,
+        percentage,
+        level,
+        frame_period,
+        animation_frames,
+        history,
+        value,
+        fields,
+        changed,
+    );
+    fn display(&self) -> impl Display {
+        if (self.predicate)(&self.metric) {
+            Either::Left(self.metric.display())
+        } else {
+            Either::Right("")
+        }
+    }
+    fn write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        if (self.predicate)(&self.metric) {
+            self.metric.write_field(name, out)
+        } else {
+            None
+        }
+    }
+}
+
+/// Uses `secondary` while `primary` fails to update. The primary metric is
+/// tried first on every update, so it takes over again once it recovers.
+#[derive(Debug, Clone)]
+pub struct Fallback<A, B> {
+    primary: A,
+    secondary: B,
+    failed: Cell<bool>,
+}
+
+impl<A, B> Fallback<A, B> {
+    fn active(&self) -> Either<&A, &B> {
+        if self.failed.get() {
+            Either::Right(&self.secondary)
+        } else {
+            Either::Left(&self.primary)
+        }
+    }
+}
+
+impl<A: Metric, B: Metric> Metric for Fallback<A, B> {
+    fn name(&self) -> &'static str {
+        self.primary.name()
+    }
+    fn display(&self) -> impl Display {
+        match self.active() {
+            Either::Left(a) => Either::Left(a.display()),
+            Either::Right(b) => Either::Right(b.display()),
+        }
+    }
+    /// Fails with the error of the fallback if both fail.
+    fn update(&self) -> impl Future<Output = Result<(), impl Error>> + '_ {
+        async {
+            match self.primary.update().await {
+                Ok(()) => {
+                    self.failed.set(false);
+                    Ok(())
+                }
+                Err(err) => {
+                    log::debug!("{} falls back: {err}", self.primary.name());
+                    self.failed.set(true);
+                    self.secondary.update().await
+                }
+            }
+        }
+    }
+    fn tooltip(&self) -> impl Display {
+        match self.active() {
+            Either::Left(a) => Either::Left(a.tooltip()),
+            Either::Right(b) => Either::Right(b.tooltip()),
+        }
+    }
+    fn percentage(&self) -> Option<u8> {
+        match self.active() {
+            Either::Left(a) => a.percentage(),
+            Either::Right(b) => b.percentage(),
+        }
+    }
+    fn level(&self) -> Level {
+        match self.active() {
+            Either::Left(a) => a.level(),
+            Either::Right(b) => b.level(),
+        }
+    }
+    fn frame_period(&self) -> Option<Duration> {
+        match self.active() {
+            Either::Left(a) => a.frame_period(),
+            Either::Right(b) => b.frame_period(),
+        }
+    }
+    fn animation_frames(&self) -> Option<(char, char)> {
+        match self.active() {
+            Either::Left(a) => a.animation_frames(),
+            Either::Right(b) => b.animation_frames(),
+        }
+    }
+    fn history(&self) -> Option<&History> {
+        match self.active() {
+            Either::Left(a) => a.history(),
+            Either::Right(b) => b.history(),
+        }
+    }
+    fn value(&self) -> Option<Value> {
+        match self.active() {
+            Either::Left(a) => a.value(),
+            Either::Right(b) => b.value(),
+        }
+    }
+    /// Only the fields of the primary metric can be used in templates.
+    fn fields(&self) -> &'static [&'static str] {
+        self.primary.fields()
+    }
+    fn write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        match self.active() {
+            Either::Left(a) => a.write_field(name, out),
+            Either::Right(b) => b.write_field(name, out),
+        }
+    }
+    /// Either metric may take over on the next update, so both are watched.
+    fn changed(&self) -> impl Future<Output = ()> + '_ {
+        async {
+            tokio::select! {
+                () = self.primary.changed() => {}
+                () = self.secondary.changed() => {}
+            }
+        }
+    }
+}
+
+/// Skips updates that come less than `min_interval` after the last one, for
+/// metrics that are expensive to sample but shown in a fast loop.
+#[derive(Debug, Clone)]
+pub struct Throttle<M> {
+    metric: M,
+    min_interval: Duration,
+    last_update: Cell<Option<Instant>>,
+}
+
+impl<M: Metric> Metric for Throttle<M> {
+    forward_metric!(
+        metric: name,
+        display,
+        tooltip,
+        percentage,
+        level,
+        frame_period,
+        animation_frames,
+        history,
+        value,
+        fields,
+        write_field,
+        changed,
+    );
+    fn update(&self) -> impl Future<Output = Result<(), impl Error>> + '_ {
+        async {
+            let now = Instant::now();
+            if self.last_update.get().is_some_and(|last| now - last < self.min_interval) {
+                return Ok(());
+            }
+            self.last_update.set(Some(now));
+            self.metric.update().await
+        }
+    }
+}
+
+/// Post-processes the text of the metric with `f`, which gets the rendered
+/// text and writes the new one. Texts longer than [`MAP_CAPACITY`] fail to
+/// format.
+#[derive(Debug, Clone)]
+pub struct Map<M, F> {
+    metric: M,
+    f: F,
+}
+
+struct Mapped<'a, M, F>(&'a Map<M, F>);
+
+impl<M: Metric, F: Fn(&str, &mut Formatter<'_>) -> fmt::Result> Display for Mapped<'_, M, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut text = heapless::String::<MAP_CAPACITY>::new();
+        write!(text, "{}", self.0.metric.display())?;
+        (self.0.f)(&text, f)
+    }
+}
+
+impl<M: Metric, F: Fn(&str, &mut Formatter<'_>) -> fmt::Result> Metric for Map<M, F> {
+    forward_metric!(
+        metric: name,
+        update,
+        tooltip,
+        percentage,
+        level,
+        frame_period,
+        animation_frames,
+        history,
+        value,
+        fields,
+        write_field,
+        changed,
+    );
+    fn display(&self) -> impl Display {
+        Mapped(self)
+    }
+}
+
+/// Constructors of the combinators for every [`Metric`].
+pub trait MetricExt: Metric + Sized {
+    /// See [`Hidden`].
+    #[must_use]
+    fn show_if<F: Fn(&Self) -> bool>(self, predicate: F) -> Hidden<Self, F> {
+        Hidden { metric: self, predicate }
+    }
+
+    /// See [`Fallback`].
+    #[must_use]
+    fn or_else<B: Metric>(self, fallback: B) -> Fallback<Self, B> {
+        Fallback { primary: self, secondary: fallback, failed: Cell::new(false) }
+    }
+
+    /// See [`Throttle`].
+    #[must_use]
+    fn throttle(self, min_interval: Duration) -> Throttle<Self> {
+        Throttle { metric: self, min_interval, last_update: Cell::new(None) }
+    }
+
+    /// See [`Map`].
+    #[must_use]
+    fn map<F: Fn(&str, &mut Formatter<'_>) -> fmt::Result>(self, f: F) -> Map<Self, F> {
+        Map { metric: self, f }
+    }
+}
+
+impl<M: Metric> MetricExt for M {}
@@ -0,0 +1,138 @@
+//! Optional configuration file made of `key = value` lines. Keys before the
+//! first `[section]` header are global, every section configures the metric
+//! with the matching name, e.g.
+//!
+//! ```text
+//! warning_color = #e5c07b
+//!
+//! [cpu]
+//! color = #8be9fd
+//! action = alacritty -e htop
+//! ```
+
+use std::{path::PathBuf, str::FromStr};
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read {}: {source}", path.display())]
+    Io { path: PathBuf, source: std::io::Error },
+    #[error("Line {0}: {1}")]
+    Syntax(usize, &'static str),
+    #[error("Invalid value for `{key}` in [{section}]: {message}")]
+    Value { section: String, key: String, message: String },
+}
+
+#[derive(Debug, Default)]
+struct Section {
+    name: String,
+    entries: Vec<(String, String)>,
+}
+
+#[derive(Debug)]
+pub struct Config {
+    sections: Vec<Section>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self { sections: vec![Section::default()] }
+    }
+}
+
+/// Compares metric names ignoring case and anything but letters and digits, so
+/// `bluetoothcharge` selects "Bluetooth Charge".
+pub fn name_matches(name: &str, query: &str) -> bool {
+    fn normalize(s: &str) -> impl Iterator<Item = char> + '_ {
+        s.chars().filter(char::is_ascii_alphanumeric).map(|c| c.to_ascii_lowercase())
+    }
+    normalize(name).eq(normalize(query))
+}
+
+impl Config {
+    /// `$BARSTATUS_CONFIG`, or `barstatus/config` in the XDG config directory.
+    fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("BARSTATUS_CONFIG") {
+            return Some(path.into());
+        }
+        let dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(dir.join("barstatus").join("config"))
+    }
+
+    /// Loads the configuration file. A missing file is an empty configuration.
+    pub fn load() -> Result<Self, ConfigError> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => Self::parse(&contents),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(ConfigError::Io { path, source: err }),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+
+        for (number, line) in contents.lines().enumerate() {
+            let number = number + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[') {
+                let name =
+                    name.strip_suffix(']').ok_or(ConfigError::Syntax(number, "Expected `]`"))?;
+                config.sections.push(Section { name: name.trim().into(), entries: Vec::new() });
+                continue;
+            }
+            let (key, value) =
+                line.split_once('=').ok_or(ConfigError::Syntax(number, "Expected `=`"))?;
+            let value = value.trim();
+            let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+            if let Some(section) = config.sections.last_mut() {
+                section.entries.push((key.trim().into(), value.into()));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Value of `key` in the section for metric `section`, or in the global
+    /// section if `section` is empty.
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.sections
+            .iter()
+            .filter(|s| {
+                if section.is_empty() {
+                    s.name.is_empty()
+                } else {
+                    name_matches(&s.name, section)
+                }
+            })
+            .flat_map(|s| s.entries.iter())
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn parse_value<T: FromStr>(
+        &self,
+        section: &str,
+        key: &str,
+    ) -> Result<Option<T>, ConfigError>
+    where
+        T::Err: std::fmt::Display,
+    {
+        self.get(section, key)
+            .map(|value| {
+                value.parse().map_err(|err: T::Err| ConfigError::Value {
+                    section: section.into(),
+                    key: key.into(),
+                    message: err.to_string(),
+                })
+            })
+            .transpose()
+    }
+}
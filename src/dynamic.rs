@@ -0,0 +1,156 @@
+//! [`DynMetric`], the object-safe side of [`Metric`], for metrics only known
+//! at runtime, e.g. a `Vec` of them picked from the configuration.
+
+use std::{
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+    future::Future,
+    pin::Pin,
+    time::Duration,
+};
+
+use crate::{history::History, Level, Metric, Value};
+
+/// A [`Metric`] behind a pointer. Every metric is one, and a
+/// `Box<dyn DynMetric>` is a metric again, so it nests in the combinators and
+/// goes in a [`StatusBar`](crate::StatusBar) like any other.
+pub trait DynMetric {
+    fn dyn_name(&self) -> &'static str;
+    /// Writes [`Metric::display`].
+    ///
+    /// # Errors
+    /// If the metric fails to format.
+    fn dyn_display(&self, f: &mut Formatter<'_>) -> fmt::Result;
+    fn dyn_update(&self) -> Pin<Box<dyn Future<Output = Result<(), DynError<'_>>> + '_>>;
+    /// Writes [`Metric::tooltip`].
+    ///
+    /// # Errors
+    /// If the metric fails to format.
+    fn dyn_tooltip(&self, f: &mut Formatter<'_>) -> fmt::Result;
+    fn dyn_percentage(&self) -> Option<u8>;
+    fn dyn_level(&self) -> Level;
+    fn dyn_frame_period(&self) -> Option<Duration>;
+    fn dyn_animation_frames(&self) -> Option<(char, char)>;
+    fn dyn_history(&self) -> Option<&History>;
+    fn dyn_value(&self) -> Option<Value>;
+    fn dyn_fields(&self) -> &'static [&'static str];
+    fn dyn_write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result>;
+    fn dyn_changed(&self) -> Pin<Box<dyn Future<Output = ()> + '_>>;
+}
+
+/// The error of a failed [`DynMetric::dyn_update`].
+pub struct DynError<'a>(Box<dyn Error + 'a>);
+
+impl Debug for DynError<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for DynError<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Error for DynError<'_> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
+
+impl<M: Metric> DynMetric for M {
+    fn dyn_name(&self) -> &'static str {
+        self.name()
+    }
+    fn dyn_display(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.display())
+    }
+    fn dyn_update(&self) -> Pin<Box<dyn Future<Output = Result<(), DynError<'_>>> + '_>> {
+        Box::pin(async { self.update().await.map_err(|err| DynError(Box::new(err))) })
+    }
+    fn dyn_tooltip(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tooltip())
+    }
+    fn dyn_percentage(&self) -> Option<u8> {
+        self.percentage()
+    }
+    fn dyn_level(&self) -> Level {
+        self.level()
+    }
+    fn dyn_frame_period(&self) -> Option<Duration> {
+        self.frame_period()
+    }
+    fn dyn_animation_frames(&self) -> Option<(char, char)> {
+        self.animation_frames()
+    }
+    fn dyn_history(&self) -> Option<&History> {
+        self.history()
+    }
+    fn dyn_value(&self) -> Option<Value> {
+        self.value()
+    }
+    fn dyn_fields(&self) -> &'static [&'static str] {
+        self.fields()
+    }
+    fn dyn_write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        self.write_field(name, out)
+    }
+    fn dyn_changed(&self) -> Pin<Box<dyn Future<Output = ()> + '_>> {
+        Box::pin(self.changed())
+    }
+}
+
+/// Text of a [`DynMetric`], written by `write`.
+struct Text<'a, 'm> {
+    metric: &'a (dyn DynMetric + 'm),
+    write: fn(&(dyn DynMetric + 'm), &mut Formatter<'_>) -> fmt::Result,
+}
+
+impl Display for Text<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        (self.write)(self.metric, f)
+    }
+}
+
+impl Metric for Box<dyn DynMetric + '_> {
+    fn name(&self) -> &'static str {
+        self.as_ref().dyn_name()
+    }
+    fn display(&self) -> impl Display {
+        Text { metric: self.as_ref(), write: |metric, f| metric.dyn_display(f) }
+    }
+    fn update(&self) -> impl Future<Output = Result<(), impl Error>> + '_ {
+        self.as_ref().dyn_update()
+    }
+    fn tooltip(&self) -> impl Display {
+        Text { metric: self.as_ref(), write: |metric, f| metric.dyn_tooltip(f) }
+    }
+    fn percentage(&self) -> Option<u8> {
+        self.as_ref().dyn_percentage()
+    }
+    fn level(&self) -> Level {
+        self.as_ref().dyn_level()
+    }
+    fn frame_period(&self) -> Option<Duration> {
+        self.as_ref().dyn_frame_period()
+    }
+    fn animation_frames(&self) -> Option<(char, char)> {
+        self.as_ref().dyn_animation_frames()
+    }
+    fn history(&self) -> Option<&History> {
+        self.as_ref().dyn_history()
+    }
+    fn value(&self) -> Option<Value> {
+        self.as_ref().dyn_value()
+    }
+    fn fields(&self) -> &'static [&'static str] {
+        self.as_ref().dyn_fields()
+    }
+    fn write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        self.as_ref().dyn_write_field(name, out)
+    }
+    fn changed(&self) -> impl Future<Output = ()> + '_ {
+        self.as_ref().dyn_changed()
+    }
+}
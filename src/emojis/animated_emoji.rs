@@ -1,5 +1,5 @@
 use super::animated_emoji_builder::{AnimatedEmojiBuilder, FramesNotSet, MaxFrequencyNotSet};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct AnimatedEmoji<'a> {
@@ -24,12 +24,17 @@ impl<'a> AnimatedEmoji<'a> {
     pub fn builder() -> AnimatedEmojiBuilder<MaxFrequencyNotSet, FramesNotSet> {
         AnimatedEmojiBuilder::default()
     }
+
+    fn fps(&self, speed: f64) -> f64 {
+        assert!((0.0..=1.0).contains(&speed), "Speed must be a value between 0 and 1");
+        let frequency = self.min_frequency + speed * (self.max_frequency - self.min_frequency);
+        self.frames.len() as f64 * frequency
+    }
+
     /// # Panics
     /// if speed is not a value between 0 and 1
     pub fn next_frame(&mut self, speed: f64) -> char {
-        assert!((0.0..=1.0).contains(&speed), "Speed must be a value between 0 and 1");
-        let frequency = self.min_frequency + speed * (self.max_frequency - self.min_frequency);
-        let fps = self.frames.len() as f64 * frequency;
+        let fps = self.fps(speed);
         let elapsed = self.previous_frame_update.map(|it| it.elapsed());
 
         let frames_to_skip = elapsed.map_or(1., |it| it.as_secs_f64() * fps);
@@ -44,6 +49,28 @@ impl<'a> AnimatedEmoji<'a> {
         self.frames[self.frame]
     }
 
+    /// How long until [`Self::next_frame`] with the same `speed` returns a
+    /// different frame.
+    ///
+    /// # Panics
+    /// if speed is not a value between 0 and 1
+    #[must_use]
+    pub fn until_next_frame(&self, speed: f64) -> Duration {
+        let period = Duration::try_from_secs_f64(1. / self.fps(speed)).unwrap_or(Duration::MAX);
+        self.previous_frame_update
+            .map_or(Duration::ZERO, |it| period.saturating_sub(it.elapsed()))
+    }
+
+    #[must_use]
+    pub fn current_frame(&self) -> char {
+        self.frames[self.frame]
+    }
+
+    #[must_use]
+    pub fn peek_next_frame(&self) -> char {
+        self.frames[(self.frame + 1) % self.frames.len()]
+    }
+
     pub fn reset(&mut self) {
         self.frame = 0;
         self.previous_frame_update = None;
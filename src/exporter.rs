@@ -0,0 +1,91 @@
+//! A tiny HTTP endpoint exposing metric values in the Prometheus text format.
+
+use std::{fmt::Write as _, net::SocketAddr, time::Duration};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// Clients that do not send a request in time are dropped, so a stuck client
+/// cannot block the next scrape.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Pause after a failed `accept`, which tends to fail again right away, e.g.
+/// while out of file descriptors.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Prometheus text exposition being built for a single scrape.
+#[derive(Debug, Default)]
+pub struct Exposition(String);
+
+impl Exposition {
+    /// Adds a gauge. Nothing is written if `value` is `None`.
+    pub fn gauge(&mut self, name: &str, help: &str, value: Option<f64>) {
+        if let Some(value) = value {
+            // Writing to a `String` cannot fail.
+            _ = write!(self.0, "# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}\n");
+        }
+    }
+}
+
+/// Serves `/metrics` on `addr`, calling `render` for every scrape. Requests
+/// are handled one at a time, which is plenty for a local scraper.
+///
+/// Returns only if binding `addr` fails, a connection that cannot be accepted
+/// is logged and skipped.
+pub async fn serve(addr: SocketAddr, render: impl Fn(&mut Exposition)) -> std::io::Error {
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(err) => return err,
+    };
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                log::warn!("Error accepting a metrics client: {err}");
+                tokio::time::sleep(ACCEPT_BACKOFF).await;
+                continue;
+            }
+        };
+        match tokio::time::timeout(REQUEST_TIMEOUT, respond(stream, &render)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => log::warn!("Error while serving metrics: {err}"),
+            Err(_) => log::warn!("Metrics client timed out"),
+        }
+    }
+}
+
+async fn respond(mut stream: TcpStream, render: impl Fn(&mut Exposition)) -> std::io::Result<()> {
+    let mut request = [0; 1024];
+    let mut read = 0;
+    while !request[..read].windows(4).any(|w| w == b"\r\n\r\n") && read < request.len() {
+        match stream.read(&mut request[read..]).await? {
+            0 => break,
+            n => read += n,
+        }
+    }
+
+    let request_line = request[..read].split(|&b| b == b'\r').next().unwrap_or_default();
+    let mut parts = request_line.split(|&b| b == b' ');
+    let (method, path) = (parts.next(), parts.next());
+
+    let (status, content_type, body) = match (method, path) {
+        (Some(b"GET"), Some(b"/metrics")) => {
+            let mut exposition = Exposition::default();
+            render(&mut exposition);
+            ("200 OK", "text/plain; version=0.0.4", exposition.0)
+        }
+        (Some(b"GET"), _) => ("404 Not Found", "text/plain", "Not found\n".into()),
+        _ => ("405 Method Not Allowed", "text/plain", "Method not allowed\n".into()),
+    };
+
+    let header = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(header.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await
+}
@@ -0,0 +1,146 @@
+use std::{
+    cell::RefCell,
+    fmt::{self, Display, Formatter},
+    time::{Duration, Instant},
+};
+
+use heapless::HistoryBuffer;
+
+/// Default number of samples kept: 5 minutes of CPU usage, 17 of network rates.
+pub const DEFAULT_CAPACITY: usize = 512;
+
+/// Averages closer than this fraction of the observed range count as steady.
+const TREND_TOLERANCE: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub at: Instant,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub min: f64,
+    pub max: f64,
+    pub average: f64,
+    pub count: usize,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Trend {
+    Rising,
+    Falling,
+    #[default]
+    Steady,
+}
+
+impl Display for Trend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Trend::Rising => "↗",
+            Trend::Falling => "↘",
+            Trend::Steady => "→",
+        })
+    }
+}
+
+/// Fixed-capacity history of a metric's values. The oldest samples are
+/// overwritten once it is full, so it never allocates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct History<const N: usize = DEFAULT_CAPACITY>(RefCell<HistoryBuffer<Sample, N>>);
+
+impl<const N: usize> Default for History<N> {
+    fn default() -> Self {
+        Self(RefCell::new(HistoryBuffer::new()))
+    }
+}
+
+impl<const N: usize> History<N> {
+    pub fn push(&self, value: f64) {
+        self.0.borrow_mut().write(Sample { at: Instant::now(), value });
+    }
+
+    pub fn clear(&self) {
+        self.0.borrow_mut().clear();
+    }
+
+    #[must_use]
+    pub fn latest(&self) -> Option<Sample> {
+        self.0.borrow().recent().copied()
+    }
+
+    /// Calls `f` with the samples from the oldest to the newest.
+    pub fn with_samples<R>(
+        &self,
+        f: impl FnOnce(&mut dyn DoubleEndedIterator<Item = Sample>) -> R,
+    ) -> R {
+        let buffer = self.0.borrow();
+        let (older, newer) = buffer.as_slices();
+        f(&mut older.iter().chain(newer).copied())
+    }
+
+    /// Calls `f` with the samples not older than `window`, from the oldest to
+    /// the newest.
+    pub fn with_window<R>(
+        &self,
+        window: Duration,
+        f: impl FnOnce(&mut dyn Iterator<Item = Sample>) -> R,
+    ) -> R {
+        let now = Instant::now();
+        self.with_samples(|samples| {
+            f(&mut samples.skip_while(|sample| now.duration_since(sample.at) > window))
+        })
+    }
+
+    /// Minimum, maximum and average over the last `window`.
+    #[must_use]
+    pub fn summary(&self, window: Duration) -> Option<Summary> {
+        self.with_window(window, |samples| {
+            let first = samples.next()?.value;
+            let mut summary = Summary { min: first, max: first, average: first, count: 1 };
+            for Sample { value, .. } in samples {
+                summary.min = summary.min.min(value);
+                summary.max = summary.max.max(value);
+                summary.average += value;
+                summary.count += 1;
+            }
+            summary.average /= summary.count as f64;
+            Some(summary)
+        })
+    }
+
+    /// Compares the average of the older half of the last `window` with the
+    /// newer one.
+    #[must_use]
+    pub fn trend(&self, window: Duration) -> Trend {
+        let now = Instant::now();
+        let buffer = self.0.borrow();
+        let (older, newer) = buffer.as_slices();
+        let values = || {
+            older
+                .iter()
+                .chain(newer)
+                .skip_while(|sample| now.duration_since(sample.at) > window)
+                .map(|sample| sample.value)
+        };
+
+        let count = values().count();
+        let half = count / 2;
+        if half == 0 {
+            return Trend::Steady;
+        }
+        let older = values().take(count - half).sum::<f64>() / (count - half) as f64;
+        let newer = values().skip(count - half).sum::<f64>() / half as f64;
+        let min = values().fold(f64::INFINITY, f64::min);
+        let max = values().fold(f64::NEG_INFINITY, f64::max);
+
+        let tolerance = (max - min) * TREND_TOLERANCE;
+        if newer - older > tolerance {
+            Trend::Rising
+        } else if older - newer > tolerance {
+            Trend::Falling
+        } else {
+            Trend::Steady
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Icon themes: the glyphs metrics put in front of their values, and the cat
+//! of [`CpuMetric`](crate::metrics::CpuMetric).
+
+use std::{borrow::Cow, str::FromStr};
+
+/// Family of the font with the cat glyphs, `fonts/Ddystopian.otf`.
+pub const CAT_FONT: &str = "Ddystopian";
+
+/// The Private Use Area glyphs of [`CAT_FONT`].
+mod pua {
+    #![allow(dead_code)]
+
+    const fn range_chars<const N: usize>(base: char) -> [char; N] {
+        let mut r = ['0'; N];
+        let mut i = 0;
+        while i < N {
+            r[i] = match core::char::from_u32(base as u32 + i as u32) {
+                Some(c) => c,
+                None => panic!(), // compile time
+            };
+            i += 1;
+        }
+        r
+    }
+
+    pub const SLEEPING_CAT_OLD: [char; 15] = range_chars('\u{e000}');
+    pub const RUNNING_CAT_OLD: [char; 5] = range_chars('\u{e100}');
+    pub const SLEEPING_CAT_NEW: [char; 15] = range_chars('\u{e200}');
+    pub const RUNNING_CAT_NEW: [char; 16] = range_chars('\u{e300}');
+}
+
+const ASCII_SLEEPING_CAT: [char; 2] = ['z', 'Z'];
+const ASCII_RUNNING_CAT: [char; 4] = ['-', '\\', '|', '/'];
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Expected `emoji`, `nerdfont` or `ascii`")]
+pub struct ParseThemeError;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    #[default]
+    Emoji,
+    /// Codepoints of the Material Design set of Nerd Fonts.
+    NerdFont,
+    /// Short words like `BAT` and `MEM`, for fonts without any of the above.
+    Ascii,
+}
+
+impl FromStr for Theme {
+    type Err = ParseThemeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "emoji" => Ok(Theme::Emoji),
+            "nerdfont" | "nerd" => Ok(Theme::NerdFont),
+            "ascii" => Ok(Theme::Ascii),
+            _ => Err(ParseThemeError),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Expected `font` or `ascii`")]
+pub struct ParseCatError;
+
+/// How the CPU cat is drawn.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Cat {
+    /// The glyphs of [`CAT_FONT`], tofu without it.
+    #[default]
+    Font,
+    /// A spinner that runs and a `z` that snores, in plain ASCII.
+    Ascii,
+}
+
+impl Cat {
+    /// [`Cat::Font`] if fontconfig knows [`CAT_FONT`], [`Cat::Ascii`] otherwise.
+    /// Runs `fc-list`, so it is meant for startup.
+    #[must_use]
+    pub fn detect() -> Self {
+        let installed = std::process::Command::new("fc-list")
+            .arg(CAT_FONT)
+            .output()
+            .is_ok_and(|out| out.status.success() && !out.stdout.trim_ascii().is_empty());
+        if installed {
+            Cat::Font
+        } else {
+            log::info!("Font {CAT_FONT} not found, falling back to the ASCII cat");
+            Cat::Ascii
+        }
+    }
+
+    #[must_use]
+    pub fn running(self) -> &'static [char] {
+        match self {
+            Cat::Font => &pua::RUNNING_CAT_NEW,
+            Cat::Ascii => &ASCII_RUNNING_CAT,
+        }
+    }
+
+    #[must_use]
+    pub fn sleeping(self) -> &'static [char] {
+        match self {
+            Cat::Font => &pua::SLEEPING_CAT_OLD,
+            Cat::Ascii => &ASCII_SLEEPING_CAT,
+        }
+    }
+}
+
+impl FromStr for Cat {
+    type Err = ParseCatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "font" => Ok(Cat::Font),
+            "ascii" => Ok(Cat::Ascii),
+            _ => Err(ParseCatError),
+        }
+    }
+}
+
+type Icon = Cow<'static, str>;
+
+/// Declares [`Icons`] from a table of every icon in the emoji, Nerd Font and
+/// ASCII themes.
+macro_rules! icons {
+    ($($(#[$doc:meta])* $name:ident: $emoji:literal, $nerd_font:literal, $ascii:literal;)*) => {
+        /// The icons of every metric. Start from a [`Theme`] and replace single
+        /// icons with [`Icons::get_mut`].
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct Icons {
+            $($(#[$doc])* pub $name: Icon,)*
+            pub cat: Cat,
+        }
+
+        impl Icons {
+            /// Names of the icons, as accepted by [`Icons::get_mut`].
+            pub const NAMES: &[&str] = &[$(stringify!($name)),*];
+
+            fn theme(theme: Theme, cat: Cat) -> Self {
+                match theme {
+                    Theme::Emoji => Self { $($name: Cow::Borrowed($emoji),)* cat },
+                    Theme::NerdFont => Self { $($name: Cow::Borrowed($nerd_font),)* cat },
+                    Theme::Ascii => Self { $($name: Cow::Borrowed($ascii),)* cat },
+                }
+            }
+
+            /// The icon called `name`, one of [`Icons::NAMES`].
+            pub fn get_mut(&mut self, name: &str) -> Option<&mut Icon> {
+                Some(match name {
+                    $(stringify!($name) => &mut self.$name,)*
+                    _ => return None,
+                })
+            }
+        }
+    };
+}
+
+// The Nerd Font icons are from the Material Design set.
+icons! {
+    battery: "🔋", "\u{f06a5}", "BAT"; // nf-md-power_plug
+    charging: "🔌🔼", "\u{f0084}", "BAT+"; // nf-md-battery_charging
+    discharging: "🔋🔽", "\u{f0079}", "BAT-"; // nf-md-battery
+    bluetooth: "🎧⚡️", "\u{f02cb}", "BT"; // nf-md-headphones
+    download: "🔽", "\u{f01da}", "v"; // nf-md-download
+    upload: "🔼", "\u{f0552}", "^"; // nf-md-upload
+    layout: "🌍", "\u{f030c}", "KB"; // nf-md-keyboard
+    memory: "📝", "\u{f035b}", "MEM"; // nf-md-memory
+    updates: "🔁", "\u{f06b0}", "UPD"; // nf-md-update
+    /// The CPU is tuned for speed.
+    performance: "⚡", "\u{f0241}", "PERF"; // nf-md-flash
+    /// The CPU is tuned for battery life.
+    powersave: "🍃", "\u{f032a}", "ECO"; // nf-md-leaf
+    temperature: "🌡", "\u{f050f}", "TMP"; // nf-md-thermometer
+    /// The load is below the warning threshold.
+    load: "🟢", "\u{f0a8f}", "LD"; // nf-md-gauge_low
+    busy: "🟡", "\u{f029a}", "LD+"; // nf-md-gauge
+    overloaded: "🔴", "\u{f0a8d}", "LD!"; // nf-md-gauge_full
+    /// Tasks stall on the CPU, memory or IO.
+    pressure: "⏳", "\u{f051f}", "PSI"; // nf-md-timer_sand
+    /// The active network interface is an Ethernet or other wired one.
+    wired: "🔗", "\u{f0200}", "ETH"; // nf-md-ethernet
+    wifi: "📶", "\u{f05a9}", "WIFI"; // nf-md-wifi
+    /// The active network interface is a phone sharing its connection.
+    tethering: "📱", "\u{f011c}", "TETH"; // nf-md-cellphone
+    /// No interface has a default route.
+    offline: "🚫", "\u{f0319}", "OFF"; // nf-md-lan_disconnect
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self::new(Theme::default())
+    }
+}
+
+impl Icons {
+    /// The icons of `theme`. The cat is drawn with [`CAT_FONT`] unless the
+    /// theme is [`Theme::Ascii`].
+    #[must_use]
+    pub fn new(theme: Theme) -> Self {
+        let cat = match theme {
+            Theme::Emoji | Theme::NerdFont => Cat::Font,
+            Theme::Ascii => Cat::Ascii,
+        };
+        Self::theme(theme, cat)
+    }
+}
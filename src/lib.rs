@@ -1,11 +1,39 @@
-#![feature(async_trait_bounds)]
 #![feature(try_blocks)]
 #![feature(never_type)]
 #![forbid(unsafe_code)]
 
-use std::{error::Error, fmt::Display, future::Future};
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    future::Future,
+    str::FromStr,
+    time::Duration,
+};
 
+use history::History;
+pub use status_bar::StatusBar;
+pub use value::Value;
+
+pub use barstatus_derive::Metric;
+
+// Lets `#[derive(Metric)]` refer to `::barstatus` inside this crate too.
+extern crate self as barstatus;
+
+pub mod alert;
+pub mod combinators;
+pub mod dynamic;
+#[cfg(feature = "prometheus")]
+pub mod exporter;
+pub mod history;
+pub mod icons;
+pub mod nl80211;
+pub mod notification;
 pub(crate) mod read_line;
+pub mod sparkline;
+pub mod stats;
+pub mod status_bar;
+pub mod template;
+pub mod value;
 
 pub mod emojis {
     pub mod animated_emoji;
@@ -15,13 +43,61 @@ pub mod emojis {
     pub use animated_emoji_builder::AnimatedEmojiBuilder;
 }
 
+pub mod procfs {
+    pub mod loadavg;
+    pub mod meminfo;
+    pub mod pressure;
+    pub mod process;
+    pub mod route;
+    pub mod stat;
+    pub mod wireless;
+
+    pub use loadavg::LoadAvg;
+    pub use meminfo::MemInfo;
+    pub use pressure::Pressure;
+    pub use route::DefaultRoute;
+    pub use stat::Stat;
+    pub use wireless::Wireless;
+}
+
+pub mod sysfs {
+    pub mod net;
+
+    pub use net::Interface;
+}
+
+pub mod output {
+    pub mod dwl;
+    pub mod frame;
+    pub mod lemonbar;
+    mod line;
+    pub mod preview;
+    pub mod style;
+    pub mod tmux;
+    pub mod waybar;
+
+    pub use dwl::DwlSink;
+    pub use frame::{Frame, Segment, Sink};
+    pub use lemonbar::LemonbarSink;
+    pub use line::LineError;
+    pub use preview::PreviewSink;
+    pub use style::{Color, Palette, Style, Styles};
+    pub use tmux::TmuxSink;
+    pub use waybar::WaybarSink;
+}
+
 pub mod metrics {
     pub mod battery;
     pub mod bluetooth;
     pub mod cpu;
     pub mod date;
+    pub mod frequency;
+    pub mod load;
     pub mod mem;
     pub mod net;
+    pub mod pressure;
+    pub mod temperature;
+    pub mod top;
     pub mod update;
     pub mod xkblayout;
     // //
@@ -29,8 +105,13 @@ pub mod metrics {
     pub use bluetooth::BluetoothChargeMetric;
     pub use cpu::CpuMetric;
     pub use date::DateMetric;
+    pub use frequency::FrequencyMetric;
+    pub use load::LoadMetric;
     pub use mem::MemMetric;
     pub use net::NetMetric;
+    pub use pressure::PressureMetric;
+    pub use temperature::TemperatureMetric;
+    pub use top::TopMetric;
     pub use update::UpdatesMetric;
     pub use xkblayout::XkbLayoutMetric;
 }
@@ -51,10 +132,101 @@ pub enum CommonError {
     ReadLine(#[from] read_line::ReadLineError),
 }
 
+/// How much attention a metric currently asks for. Outputs map it to their own
+/// notion of styling, e.g. a CSS class for Waybar.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    #[default]
+    Normal,
+    Warning,
+    Critical,
+}
+
+impl Level {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Normal => "normal",
+            Level::Warning => "warning",
+            Level::Critical => "critical",
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Expected `normal`, `warning` or `critical`")]
+pub struct ParseLevelError;
+
+impl FromStr for Level {
+    type Err = ParseLevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Level::Normal),
+            "warning" => Ok(Level::Warning),
+            "critical" => Ok(Level::Critical),
+            _ => Err(ParseLevelError),
+        }
+    }
+}
+
 pub trait Metric {
     fn name(&self) -> &'static str;
     fn display(&self) -> impl Display;
     fn update(&self) -> impl Future<Output = Result<(), impl Error>> + '_;
+
+    /// Longer description of the current state, shown on hover where supported.
+    fn tooltip(&self) -> impl Display {
+        self.name()
+    }
+
+    /// Value in `0..=100` for outputs that can draw gauges.
+    fn percentage(&self) -> Option<u8> {
+        None
+    }
+
+    fn level(&self) -> Level {
+        Level::Normal
+    }
+
+    /// How soon the metric wants to be redrawn, if it is animated.
+    fn frame_period(&self) -> Option<Duration> {
+        None
+    }
+
+    /// The current and the next frame of the metric's animation, if any.
+    fn animation_frames(&self) -> Option<(char, char)> {
+        None
+    }
+
+    /// Recent values of the metric, if it has a single numeric one.
+    fn history(&self) -> Option<&History> {
+        None
+    }
+
+    /// The current value, for consumers other than the bar. `None` while it
+    /// is unknown.
+    fn value(&self) -> Option<Value> {
+        None
+    }
+
+    /// Names of the values format templates can refer to, see
+    /// [`template::Template`].
+    fn fields(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Writes the value called `name` to `out`. `None` if the metric has no
+    /// such value right now.
+    fn write_field(&self, _name: &str, _out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        None
+    }
+
+    /// Resolves when the metric should be updated before its next interval,
+    /// for metrics the kernel can notify. Never resolves by default.
+    fn changed(&self) -> impl Future<Output = ()> + '_ {
+        std::future::pending()
+    }
 }
 
 impl<T: Metric> Metric for &T {
@@ -67,6 +239,36 @@ impl<T: Metric> Metric for &T {
     fn update(&self) -> impl Future<Output = Result<(), impl Error>> + '_ {
         T::update(*self)
     }
+    fn tooltip(&self) -> impl Display {
+        T::tooltip(*self)
+    }
+    fn percentage(&self) -> Option<u8> {
+        T::percentage(*self)
+    }
+    fn level(&self) -> Level {
+        T::level(*self)
+    }
+    fn frame_period(&self) -> Option<Duration> {
+        T::frame_period(*self)
+    }
+    fn animation_frames(&self) -> Option<(char, char)> {
+        T::animation_frames(*self)
+    }
+    fn history(&self) -> Option<&History> {
+        T::history(*self)
+    }
+    fn value(&self) -> Option<Value> {
+        T::value(*self)
+    }
+    fn fields(&self) -> &'static [&'static str] {
+        T::fields(*self)
+    }
+    fn write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        T::write_field(*self, name, out)
+    }
+    fn changed(&self) -> impl Future<Output = ()> + '_ {
+        T::changed(*self)
+    }
 }
 
 #[macro_export]
@@ -1,132 +1,486 @@
 #![cfg_attr(not(feature = "xsetroot_dyn"), forbid(unsafe_code))]
 #![feature(never_type)]
 
-// todo: adaptive frame rate. We do not need 71 fps all the time, only when
-//       cat animation is running at full speed. Performance is good even at 71
-//       fps, but adaptation will make this program nearly transparent.
-
-use std::{
-    io::{Cursor, Write},
-    time::Duration,
-};
+use std::{fmt::Display, future::Future, time::Duration};
 
 use barstatus::{
-    generic_for_each,
+    alert::{Alerts, Condition, Rule, When, DEFAULT_HYSTERESIS},
+    combinators::MetricExt,
+    icons::{Cat, Icons},
     metrics::{
-        BatteryMetric, BluetoothChargeMetric, CpuMetric, DateMetric, NetMetric, UpdatesMetric,
+        cpu::Cores,
+        load::{CRITICAL_LOAD, WARNING_LOAD},
+        net::InterfaceFilter,
+        BatteryMetric, BluetoothChargeMetric, CpuMetric, DateMetric, FrequencyMetric, LoadMetric,
+        MemMetric, NetMetric, PressureMetric, TemperatureMetric, TopMetric, UpdatesMetric,
         XkbLayoutMetric,
     },
-    Metric,
+    output::{DwlSink, LemonbarSink, PreviewSink, Style, Styles, TmuxSink, WaybarSink},
+    sparkline::{Scale, Sparkline},
+    stats::Stats,
+    status_bar::Metrics,
+    template::{Formatted, Template},
+    Metric, StatusBar,
 };
-use frunk::hlist;
+use config::{name_matches, Config, ConfigError};
 
+mod config;
 mod xsetroot;
 
-const FPS: f64 = 71.;
-const LOOP_TIME: Duration = Duration::from_nanos((1_000_000_000. / FPS) as u64);
+/// tmux redraws the status line once a second at most.
+const TMUX_LOOP_TIME: Duration = Duration::from_secs(1);
+/// Long enough for the network metric to have two samples to compute rates from.
+const SNAPSHOT_DELAY: Duration = Duration::from_millis(2100);
 
-/// "Spawns" a loop that updates a metric every `interval` duration.
-/// Note that it give a stream yielding any `T` - this is because it never
-/// actually yields, so we can say we yield any `T`.
-async fn metric_interval<'a, M: Metric>(
-    name: &'static str, // note: maybe use `Metric::name`
-    interval: Duration,
-    metric: &'a M,
-) {
-    let mut interval = tokio::time::interval(interval);
-    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
-    loop {
-        interval.tick().await;
-        if let Err(err) = metric.update().await {
-            log::error!("Error in {name}: {err}");
+const USAGE: &str = "\
+Usage: barstatus [MODE]
+
+Modes:
+    xsetroot          Set the status as the root window name (default)
+    waybar [METRIC]   Print Waybar custom module JSON for one metric or the whole bar
+    lemonbar          Print lemonbar formatted lines
+    tmux [--follow]   Print a snapshot for tmux `status-right`, or keep printing
+    dwl [--color]     Print plain lines for dwl and somebar, with bar patch colours
+    preview           Draw the bar in the terminal with update statistics underneath";
+
+enum Mode {
+    XSetRoot,
+    Waybar(Option<String>),
+    Lemonbar,
+    Tmux { follow: bool },
+    Dwl { color: bool },
+    Preview,
+}
+
+impl Mode {
+    fn from_args() -> Result<Self, &'static str> {
+        let mut args = std::env::args().skip(1);
+        let mode = match args.next().as_deref() {
+            None | Some("xsetroot") => Mode::XSetRoot,
+            Some("waybar") => Mode::Waybar(args.next()),
+            Some("lemonbar") => Mode::Lemonbar,
+            Some("tmux") => match args.next().as_deref() {
+                None => Mode::Tmux { follow: false },
+                Some("--follow") => Mode::Tmux { follow: true },
+                Some(_) => return Err("Unknown tmux option"),
+            },
+            Some("dwl") => match args.next().as_deref() {
+                None => Mode::Dwl { color: false },
+                Some("--color") => Mode::Dwl { color: true },
+                Some(_) => return Err("Unknown dwl option"),
+            },
+            Some("preview") => Mode::Preview,
+            Some("-h" | "--help") => return Err(""),
+            Some(_) => return Err("Unknown mode"),
+        };
+        if args.next().is_some() {
+            return Err("Too many arguments");
+        }
+        Ok(mode)
+    }
+}
+
+fn usage(err: &str) -> ! {
+    if !err.is_empty() {
+        eprintln!("{err}\n");
+    }
+    eprintln!("{USAGE}");
+    std::process::exit(2);
+}
+
+/// Unwraps a result of the startup, exiting with a message otherwise.
+fn or_exit<T>(result: Result<T, impl Display>, context: &str) -> T {
+    result.unwrap_or_else(|err| {
+        eprintln!("{context}: {err}");
+        std::process::exit(2);
+    })
+}
+
+/// The metric selected on the command line, if any. Exits if it does not exist.
+fn only(mode: &Mode, names: &[&'static str]) -> Option<&'static str> {
+    let Mode::Waybar(Some(query)) = mode else {
+        return None;
+    };
+    let Some(&name) = names.iter().find(|name| name_matches(name, query)) else {
+        eprintln!("Unknown metric {query:?}, expected one of: {}", names.join(", "));
+        std::process::exit(2);
+    };
+    Some(name)
+}
+
+fn styles(config: &Config, names: &[&'static str]) -> Result<Styles, ConfigError> {
+    let mut styles = Styles::default();
+    if let Some(color) = config.parse_value("", "warning_color")? {
+        styles.palette.warning = color;
+    }
+    if let Some(color) = config.parse_value("", "critical_color")? {
+        styles.palette.critical = color;
+    }
+    for &name in names {
+        let style = Style {
+            color: config.parse_value(name, "color")?,
+            action: config.get(name, "action").map(Into::into),
+        };
+        styles.set(name, style);
+    }
+    Ok(styles)
+}
+
+/// The `sparkline` width and `sparkline_scale` of a metric, if it has one.
+/// Exits if they are invalid.
+fn sparkline(config: &Config, name: &str, scale: Scale) -> Option<Sparkline> {
+    let result = || -> Result<_, ConfigError> {
+        let width = config.parse_value(name, "sparkline")?;
+        let scale = config.parse_value(name, "sparkline_scale")?.unwrap_or(scale);
+        Ok(width.map(|width| Sparkline::new(width, scale)))
+    };
+    or_exit(result(), "Error in the configuration")
+}
+
+/// The icons of the `theme` of the `[icons]` section, with the single icons the
+/// section sets. Unless `cat` is set there, the cat falls back to ASCII if its
+/// font is not installed.
+fn icons(config: &Config) -> Result<Icons, ConfigError> {
+    let mut icons = Icons::new(config.parse_value("icons", "theme")?.unwrap_or_default());
+    for name in Icons::NAMES {
+        if let (Some(icon), Some(value)) = (icons.get_mut(name), config.get("icons", name)) {
+            *icon = value.to_owned().into();
+        }
+    }
+    icons.cat = match config.parse_value("icons", "cat")? {
+        Some(cat) => cat,
+        None if icons.cat == Cat::Font => Cat::detect(),
+        None => icons.cat,
+    };
+    Ok(icons)
+}
+
+/// The CPU metric with the sparkline and `cores` of `[cpu]`, not animated in
+/// tmux.
+fn cpu(config: &Config, icons: &Icons, mode: &Mode) -> Result<CpuMetric, ConfigError> {
+    let metric = CpuMetric::default().with_icons(icons);
+    let metric = match mode {
+        Mode::Tmux { .. } => metric.without_animation(),
+        _ => metric,
+    };
+    let metric = match sparkline(config, "cpu", Scale::Fixed(0., 100.)) {
+        Some(sparkline) => metric.with_sparkline(sparkline),
+        None => metric,
+    };
+    Ok(match config.parse_value::<Cores>("cpu", "cores")? {
+        Some(cores) => metric.with_cores(cores),
+        None => metric,
+    })
+}
+
+/// The memory metric with the `display` and sparkline of `[mem]`.
+fn mem(config: &Config, icons: &Icons) -> Result<MemMetric, ConfigError> {
+    let metric = MemMetric::default().with_icons(icons);
+    let metric = match sparkline(config, "mem", Scale::Fixed(0., 100.)) {
+        Some(sparkline) => metric.with_sparkline(sparkline),
+        None => metric,
+    };
+    Ok(match config.parse_value("mem", "display")? {
+        Some(display) => metric.with_display(display),
+        None => metric,
+    })
+}
+
+/// The net metric with the sparklines, the `smoothing` and the `include` and
+/// `exclude` interface patterns of `[net]`.
+fn net(config: &Config, icons: &Icons) -> Result<NetMetric, ConfigError> {
+    let default = InterfaceFilter::default();
+    let filter = InterfaceFilter {
+        include: config.parse_value("net", "include")?,
+        exclude: config.parse_value("net", "exclude")?.unwrap_or(default.exclude),
+    };
+    let smoothing = config.parse_value::<f64>("net", "smoothing")?.unwrap_or_default();
+    let smoothing = Duration::try_from_secs_f64(smoothing).map_err(|err| ConfigError::Value {
+        section: "net".into(),
+        key: "smoothing".into(),
+        message: err.to_string(),
+    })?;
+    let metric = NetMetric::default()
+        .with_icons(icons)
+        .with_filter(filter)
+        .with_smoothing(smoothing);
+    Ok(match sparkline(config, "net", Scale::Auto) {
+        Some(sparkline) => metric.with_sparklines(sparkline),
+        None => metric,
+    })
+}
+
+/// The load metric with the `display`, `warning` and `critical` of `[load]`.
+fn load(config: &Config, icons: &Icons) -> Result<LoadMetric, ConfigError> {
+    let metric = LoadMetric::default().with_icons(icons).with_thresholds(
+        config.parse_value("load", "warning")?.unwrap_or(WARNING_LOAD),
+        config.parse_value("load", "critical")?.unwrap_or(CRITICAL_LOAD),
+    );
+    Ok(match config.parse_value("load", "display")? {
+        Some(display) => metric.with_display(display),
+        None => metric,
+    })
+}
+
+/// `metric` with the `format` template of its section, if it has one. Exits if
+/// the template is invalid.
+fn formatted<M: Metric>(config: &Config, metric: M) -> Formatted<M> {
+    let name = metric.name();
+    let result = config.parse_value::<Template>(name, "format").and_then(|template| {
+        Formatted::new(metric, template).map_err(|err| ConfigError::Value {
+            section: name.into(),
+            key: "format".into(),
+            message: err.to_string(),
+        })
+    });
+    or_exit(result, "Error in the configuration")
+}
+
+/// Whether the section of `name` enables its metric, `default` if it does not
+/// say. Exits if `enabled` is not a boolean.
+fn enabled(config: &Config, name: &str, default: bool) -> bool {
+    let enabled = config.parse_value(name, "enabled");
+    or_exit(enabled, "Error in the configuration").unwrap_or(default)
+}
+
+/// The `alert_below`, `alert_above` and `alert_level` rules of every metric,
+/// sharing their section's `alert_for`, `alert_hysteresis`, `alert_rearm` and
+/// `alert_when`.
+fn alerts(config: &Config, names: &[&'static str]) -> Result<Alerts, ConfigError> {
+    let mut alerts = Alerts::default();
+    for &name in names {
+        let sustain = Duration::from_secs(config.parse_value(name, "alert_for")?.unwrap_or(0));
+        let hysteresis =
+            config.parse_value(name, "alert_hysteresis")?.unwrap_or(DEFAULT_HYSTERESIS);
+        let rearm = config.parse_value(name, "alert_rearm")?.map(Duration::from_secs);
+        let when = config.parse_value::<When>(name, "alert_when")?;
+        let conditions = [
+            config.parse_value(name, "alert_below")?.map(Condition::Below),
+            config.parse_value(name, "alert_above")?.map(Condition::Above),
+            config.parse_value(name, "alert_level")?.map(Condition::Level),
+        ];
+        for condition in conditions.into_iter().flatten() {
+            let rule = Rule::new(condition).sustained_for(sustain).with_hysteresis(hysteresis);
+            let rule = match rearm {
+                Some(rearm) => rule.rearmed_after(rearm),
+                None => rule,
+            };
+            alerts.push(
+                name,
+                match &when {
+                    Some(when) => rule.when(when.clone()),
+                    None => rule,
+                },
+            );
+        }
+    }
+    Ok(alerts)
+}
+
+/// Logs why the bar stopped and exits.
+fn stopped(context: &str, err: &dyn Display) -> ! {
+    log::error!("{context}: {err}");
+    std::process::exit(1);
+}
+
+/// Runs `bar` with the output selected by `mode`, and `task` alongside.
+fn output<L: Metrics>(
+    mode: &Mode,
+    bar: StatusBar<'_, L>,
+    only: Option<&'static str>,
+    styles: Styles,
+    stats: &Stats,
+    task: impl Future<Output = ()>,
+) -> ! {
+    let stdout = std::io::stdout().lock();
+    match mode {
+        Mode::XSetRoot => {
+            let err = bar.run_with(xsetroot::XSetRootSink, task);
+            stopped("Error while setting on bar", &err)
+        }
+        Mode::Waybar(_) => {
+            let err = bar.run_with(WaybarSink::new(stdout, only), task);
+            stopped("Error while writing to Waybar", &err)
+        }
+        Mode::Lemonbar => {
+            let err = bar.run_with(LemonbarSink::new(stdout, styles), task);
+            stopped("Error while writing to lemonbar", &err)
+        }
+        Mode::Tmux { follow: true } => {
+            let bar = bar.min_period(TMUX_LOOP_TIME);
+            let err = bar.run_with(TmuxSink::new(stdout, styles), task);
+            stopped("Error while writing to tmux", &err)
+        }
+        Mode::Tmux { follow: false } => {
+            match bar.run_once(TmuxSink::new(stdout, styles), SNAPSHOT_DELAY) {
+                Ok(()) => std::process::exit(0),
+                Err(err) => stopped("Error while writing to tmux", &err),
+            }
+        }
+        Mode::Dwl { color } => {
+            let err = bar.run_with(DwlSink::new(stdout, (*color).then_some(styles)), task);
+            stopped("Error while writing to dwl", &err)
+        }
+        Mode::Preview => {
+            let err = bar.run_with(PreviewSink::new(stdout, styles, stats), task);
+            stopped("Error while writing to the terminal", &err)
         }
     }
 }
 
+/// Serves the metrics endpoint on `prometheus_listen`, if it is set.
+#[cfg(feature = "prometheus")]
+async fn export(config: &Config, render: impl Fn(&mut barstatus::exporter::Exposition)) {
+    let listen = config.parse_value::<std::net::SocketAddr>("", "prometheus_listen");
+    if let Some(addr) = or_exit(listen, "Error in the configuration") {
+        let err = barstatus::exporter::serve(addr, render).await;
+        log::error!("Cannot serve metrics on {addr}: {err}");
+    }
+}
+
+#[cfg(feature = "prometheus")]
+fn expose(
+    exposition: &mut barstatus::exporter::Exposition,
+    net: &NetMetric,
+    cpu: &CpuMetric,
+    battery: &BatteryMetric,
+    bluetooth: &BluetoothChargeMetric,
+    updates: &UpdatesMetric,
+) {
+    let (download, upload) = net.rates();
+    exposition.gauge("barstatus_cpu_usage_percent", "CPU usage", cpu.percentage().map(f64::from));
+    exposition.gauge(
+        "barstatus_network_receive_bytes_per_second",
+        "Download rate over the counted interfaces",
+        Some(download as f64),
+    );
+    exposition.gauge(
+        "barstatus_network_transmit_bytes_per_second",
+        "Upload rate over the counted interfaces",
+        Some(upload as f64),
+    );
+    exposition.gauge(
+        "barstatus_battery_percent",
+        "Battery charge",
+        battery.percentage().map(f64::from),
+    );
+    exposition.gauge(
+        "barstatus_bluetooth_charge_percent",
+        "Charge of the connected bluetooth device",
+        bluetooth.percentage().map(f64::from),
+    );
+    exposition.gauge(
+        "barstatus_updates_pending",
+        "Number of pending system updates",
+        Some(updates.count() as f64),
+    );
+}
+
 fn main() {
     env_logger::init();
 
-    let rt = tokio::runtime::Builder::new_current_thread()
-        .enable_all()
-        .build()
-        .expect("Failed to build tokio runtime");
+    let mode = Mode::from_args().unwrap_or_else(|err| usage(err));
+
+    let config = or_exit(Config::load(), "Error in the configuration");
+    let icons = or_exit(icons(&config), "Error in the configuration");
 
-    let net_metric = NetMetric::default();
-    let cpu_metric = CpuMetric::default();
+    let net_metric = or_exit(net(&config, &icons), "Error in the configuration");
+    let cpu_metric = or_exit(cpu(&config, &icons, &mode), "Error in the configuration");
+    let frequency_metric = FrequencyMetric::default().with_icons(&icons);
+    let aggregate = config.parse_value("frequency", "aggregate");
+    let frequency_metric = match or_exit(aggregate, "Error in the configuration") {
+        Some(aggregate) => frequency_metric.with_aggregate(aggregate),
+        None => frequency_metric,
+    };
+    let temperature_metric = TemperatureMetric::default().with_icons(&icons);
+    let temperature_metric = match config.get("temperature", "sensor") {
+        Some(sensor) => temperature_metric.with_sensor(sensor),
+        None => temperature_metric,
+    };
+    let load_metric = or_exit(load(&config, &icons), "Error in the configuration");
+    let pressure_metric = PressureMetric::default().with_icons(&icons);
+    let threshold = config.parse_value("pressure", "warning");
+    let pressure_metric = match or_exit(threshold, "Error in the configuration") {
+        Some(threshold) => pressure_metric.with_threshold(threshold),
+        None => pressure_metric,
+    };
+    let top_metric = TopMetric::default();
+    let top_metric = match or_exit(config.parse_value("top", "sort"), "Error in the configuration")
+    {
+        Some(sort) => top_metric.with_sort(sort),
+        None => top_metric,
+    };
+    let mem_metric = or_exit(mem(&config, &icons), "Error in the configuration");
     // bluetoothctl, grep, sed
-    let bluetooth_metric = BluetoothChargeMetric::default();
+    let bluetooth_metric = BluetoothChargeMetric::default().with_icons(&icons);
     // xkb-switch
-    let xkb_metric = XkbLayoutMetric::default();
+    let xkb_metric = XkbLayoutMetric::default().with_icons(&icons);
     // checkupdates
-    let updates_metric = UpdatesMetric::default();
-    let battery_metric = BatteryMetric::new(80);
+    let updates_metric = UpdatesMetric::default().with_icons(&icons);
+    let battery_metric = BatteryMetric::default().with_icons(&icons);
+    // Only worth a glance once it runs low.
+    let low_battery =
+        (&battery_metric).show_if(|battery| battery.percentage().is_some_and(|p| p < 80));
     let date_metric = DateMetric::default();
 
-    let set = tokio::task::LocalSet::new();
+    // The metrics added since the first release are off by default, so that
+    // the bar still fits where it did. A metric picked on the command line is
+    // always on.
+    let picked = |name| matches!(&mode, Mode::Waybar(Some(query)) if name_matches(name, query));
+    let on = |name| picked(name) || enabled(&config, name, true);
+    let off = |name| picked(name) || enabled(&config, name, false);
+    let bar = StatusBar::new()
+        .metric_if(on("net"), formatted(&config, &net_metric), Duration::from_secs(2))
+        .metric_if(on("cpu"), formatted(&config, &cpu_metric), Duration::from_millis(600))
+        .metric_if(off("frequency"), formatted(&config, &frequency_metric), Duration::from_secs(2))
+        .metric_if(
+            off("temperature"),
+            formatted(&config, &temperature_metric),
+            Duration::from_secs(2),
+        )
+        // The kernel recomputes the averages every 5 seconds.
+        .metric_if(off("load"), formatted(&config, &load_metric), Duration::from_secs(5))
+        // Also woken by PSI triggers when the pressure rises.
+        .metric_if(off("pressure"), formatted(&config, &pressure_metric), Duration::from_secs(10))
+        // Reads every process in /proc, so only now and then.
+        .metric_if(off("top"), formatted(&config, &top_metric), Duration::from_secs(10))
+        .metric_if(off("mem"), formatted(&config, &mem_metric), Duration::from_secs(2))
+        .metric_if(
+            on("bluetooth-charge"),
+            formatted(&config, &bluetooth_metric),
+            Duration::from_secs(5),
+        )
+        .metric_if(on("xkblayout"), formatted(&config, &xkb_metric), Duration::from_millis(300))
+        .metric_if(on("updates"), formatted(&config, &updates_metric), Duration::from_secs(60))
+        .metric_if(on("battery"), formatted(&config, &low_battery), Duration::from_secs(1))
+        .static_metric(formatted(&config, &date_metric));
 
-    let main = async {
-        let mut frame_interval = tokio::time::interval(LOOP_TIME);
-        frame_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let names = bar.names();
+    let styles = or_exit(styles(&config, &names), "Error in the configuration");
+    let alerts = or_exit(alerts(&config, &names), "Error in the configuration");
+    let stats = Stats::default();
 
-        loop {
-            frame_interval.tick().await;
-            let mut buf: [u8; 256] = [0; 256];
-            let mut writer = Cursor::new(&mut buf[..]);
+    let only = only(&mode, &names);
+    let bar = bar.only(only).alerts(&alerts);
+    let bar = match mode {
+        Mode::Preview => bar.stats(&stats),
+        _ => bar,
+    };
 
-            let mut metrics = hlist![
+    let exporter = async {
+        #[cfg(feature = "prometheus")]
+        export(&config, |exposition| {
+            expose(
+                exposition,
                 &net_metric,
                 &cpu_metric,
+                &battery_metric,
                 &bluetooth_metric,
-                &xkb_metric,
                 &updates_metric,
-                &battery_metric,
-                &date_metric
-            ];
-
-            generic_for_each!(
-                metrics,
-                &mut writer,
-                |metric: &mut impl Metric, writer: &mut Cursor<&mut [u8]>| {
-                    let prev_pos = writer.position();
-
-                    if let Err(err) = write!(writer, "{}", metric.display()) {
-                        log::error!("Error while writing metric {}: {err}", metric.name());
-                    }
-
-                    if prev_pos != writer.position() {
-                        if let Err(err) = write!(writer, " | ") {
-                            log::error!("Error while writing separator: {err}");
-                        }
-                    }
-                }
             );
-
-            let position = writer.position().min(93) as usize;
-            let Ok(line) = std::str::from_utf8(&buf[..position]) else {
-                log::error!("Error while converting to utf8 (maybe in the middle of the emoji)");
-                continue;
-            };
-            let line = line.trim_end_matches(" | ");
-
-            // Purposefully block the executor. There are no tasks except this one.
-            if let Err(e) = xsetroot::set_on_bar(line) {
-                log::error!("Error while setting on bar: {e}");
-                break;
-            };
-        }
-
-        unreachable!("Interval stream cannot end");
+        })
+        .await;
     };
 
-    rt.block_on(set.run_until(async {
-        tokio::join!(
-            main,
-            set.run_until(metric_interval("Net", Duration::from_secs(2), &net_metric)),
-            set.run_until(metric_interval("Cpu", Duration::from_millis(600), &cpu_metric)),
-            set.run_until(metric_interval("Bluetooth", Duration::from_secs(5), &bluetooth_metric)),
-            set.run_until(metric_interval("Xkb", Duration::from_millis(300), &xkb_metric)),
-            set.run_until(metric_interval("Updates", Duration::from_secs(60), &updates_metric)),
-            set.run_until(metric_interval("Battery", Duration::from_secs(1), &battery_metric)),
-        )
-    }));
+    output(&mode, bar, only, styles, &stats, exporter);
 }
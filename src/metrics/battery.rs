@@ -1,34 +1,53 @@
 use std::{
     cell::Cell,
-    fmt::{Display, Formatter},
+    fmt::{self, Display, Formatter},
 };
 
-use crate::{read_line::read_line_from_path, CommonError, Metric};
+use crate::{
+    history::History,
+    icons::Icons,
+    read_line::read_line_from_path,
+    value::{ChargeState, Value},
+    CommonError, Level, Metric,
+};
+
+const WARNING_PERCENTAGE: u8 = 30;
+const CRITICAL_PERCENTAGE: u8 = 15;
 
-#[derive(Debug, Clone)]
+#[derive(Default, Debug, Clone)]
 pub struct BatteryMetric {
-    threshold: u8,
     display: Cell<DisplayBattery>,
+    history: History,
+    icons: Icons,
 }
 
 #[derive(Default, Debug, Clone, Copy)]
-struct DisplayBattery(Option<&'static str>, Option<u8>, u8);
+struct DisplayBattery(Option<ChargeState>, Option<u8>);
 
 impl BatteryMetric {
+    /// Shows the battery icons of `icons`.
     #[must_use]
-    pub fn new(threshold: u8) -> Self {
-        Self { threshold, display: Default::default() }
+    pub fn with_icons(self, icons: &Icons) -> Self {
+        Self { icons: icons.clone(), ..self }
     }
 
-    async fn emoji(&self) -> Result<&'static str, CommonError> {
+    async fn status(&self) -> Result<ChargeState, CommonError> {
         Ok(match read_line_from_path::<24>("/sys/class/power_supply/BAT0/status").await? {
-            status if status.trim() == "Charging" => "🔌🔼",
-            status if status.trim() == "Discharging" => "🔋🔽",
-            _ => "🔋",
+            status if status.trim() == "Charging" => ChargeState::Charging,
+            status if status.trim() == "Discharging" => ChargeState::Discharging,
+            _ => ChargeState::Idle,
         })
     }
 
-    async fn percentage(&self) -> Result<u8, CommonError> {
+    fn icon(&self, status: ChargeState) -> &str {
+        match status {
+            ChargeState::Charging => &self.icons.charging,
+            ChargeState::Discharging => &self.icons.discharging,
+            ChargeState::Idle => &self.icons.battery,
+        }
+    }
+
+    async fn capacity(&self) -> Result<u8, CommonError> {
         let percentage = read_line_from_path::<24>("/sys/class/power_supply/BAT0/capacity");
 
         Ok(percentage.await?.trim().parse::<u8>()?)
@@ -41,33 +60,91 @@ impl Metric for BatteryMetric {
     }
 
     fn display(&self) -> impl Display {
-        self.display.get()
+        self
     }
 
     async fn update(&self) -> Result<(), CommonError> {
         match try {
-            self.display.set(DisplayBattery(
-                Some(self.emoji().await?),
-                Some(self.percentage().await?),
-                self.threshold,
-            ));
+            let percentage = self.capacity().await?;
+            self.display.set(DisplayBattery(Some(self.status().await?), Some(percentage)));
+            self.history.push(percentage as f64);
         } {
             Ok(()) => Ok(()),
             Err(err) => {
-                self.display.set(DisplayBattery(None, None, self.threshold));
+                self.display.set(DisplayBattery::default());
                 Err(err)
             }
         }
     }
+
+    fn tooltip(&self) -> impl Display {
+        BatteryTooltip(self.display.get())
+    }
+
+    fn percentage(&self) -> Option<u8> {
+        self.display.get().1
+    }
+
+    fn history(&self) -> Option<&History> {
+        Some(&self.history)
+    }
+
+    fn value(&self) -> Option<Value> {
+        let DisplayBattery(state, percent) = self.display.get();
+        Some(Value::Charging { percent: percent?, state: state? })
+    }
+
+    fn level(&self) -> Level {
+        match self.display.get() {
+            DisplayBattery(Some(ChargeState::Discharging), Some(p)) if p <= CRITICAL_PERCENTAGE => {
+                Level::Critical
+            }
+            DisplayBattery(Some(ChargeState::Discharging), Some(p)) if p <= WARNING_PERCENTAGE => {
+                Level::Warning
+            }
+            _ => Level::Normal,
+        }
+    }
+
+    fn fields(&self) -> &'static [&'static str] {
+        &["icon", "percent", "state"]
+    }
+
+    fn write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        let DisplayBattery(Some(status), Some(percentage)) = self.display.get() else {
+            return None;
+        };
+        Some(match name {
+            "icon" => out.write_str(self.icon(status)),
+            "percent" => write!(out, "{percentage}"),
+            "state" => out.write_str(status.as_str()),
+            _ => return None,
+        })
+    }
 }
 
-impl Display for DisplayBattery {
+struct BatteryTooltip(DisplayBattery);
+
+impl Display for BatteryTooltip {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let &Self(emoji, percentage, threeshold) = self;
-        if let Some((emoji, percentage)) = emoji.zip(percentage) {
-            if percentage < threeshold {
-                write!(f, "{emoji} {percentage}%")?;
+        match self.0 {
+            DisplayBattery(Some(ChargeState::Charging), Some(percentage)) => {
+                write!(f, "Battery: {percentage}%, charging")
+            }
+            DisplayBattery(Some(ChargeState::Discharging), Some(percentage)) => {
+                write!(f, "Battery: {percentage}%, discharging")
             }
+            DisplayBattery(_, Some(percentage)) => write!(f, "Battery: {percentage}%"),
+            DisplayBattery(..) => write!(f, "Battery: unknown"),
+        }
+    }
+}
+
+impl Display for BatteryMetric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let DisplayBattery(status, percentage) = self.display.get();
+        if let Some((status, percentage)) = status.zip(percentage) {
+            write!(f, "{} {percentage}%", self.icon(status))?;
         }
 
         Ok(())
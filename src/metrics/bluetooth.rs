@@ -1,11 +1,23 @@
 use std::cell::Cell;
-use std::fmt::Display;
+use std::fmt::{self, Display};
 use tokio::process::Command;
 
-use crate::{CommonError, Metric};
+use crate::{history::History, icons::Icons, CommonError, Metric, Value};
 
 #[derive(Debug, Default)]
-pub struct BluetoothChargeMetric(Cell<Option<u8>>);
+pub struct BluetoothChargeMetric {
+    charge: Cell<Option<u8>>,
+    history: History,
+    icons: Icons,
+}
+
+impl BluetoothChargeMetric {
+    /// Shows the bluetooth icon of `icons`.
+    #[must_use]
+    pub fn with_icons(self, icons: &Icons) -> Self {
+        Self { icons: icons.clone(), ..self }
+    }
+}
 
 impl Metric for BluetoothChargeMetric {
     fn name(&self) -> &'static str {
@@ -26,27 +38,53 @@ impl Metric for BluetoothChargeMetric {
             }
             let percentage = std::str::from_utf8(&out.stdout)?.trim();
             if percentage.is_empty() {
-                self.0.set(None);
+                self.charge.set(None);
                 return Ok(());
             }
             let percentage = percentage.parse::<u8>()?;
-            self.0.set(Some(percentage));
+            self.charge.set(Some(percentage));
+            self.history.push(percentage as f64);
         };
 
         if result.is_err() {
-            self.0.set(None);
+            self.charge.set(None);
         }
 
         result
     }
+
+    fn percentage(&self) -> Option<u8> {
+        self.charge.get()
+    }
+
+    fn history(&self) -> Option<&History> {
+        Some(&self.history)
+    }
+
+    fn value(&self) -> Option<Value> {
+        self.charge.get().map(Value::Percent)
+    }
+
+    fn fields(&self) -> &'static [&'static str] {
+        &["icon", "percent"]
+    }
+
+    fn write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        let percentage = self.charge.get()?;
+        Some(match name {
+            "icon" => out.write_str(&self.icons.bluetooth),
+            "percent" => write!(out, "{percentage}"),
+            _ => return None,
+        })
+    }
 }
 
 impl Display for BluetoothChargeMetric {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let percentage = self.0.get();
+        let percentage = self.charge.get();
 
         if let Some(percentage) = percentage {
-            write!(f, "🎧⚡️ {percentage}%")?;
+            write!(f, "{} {percentage}%", self.icons.bluetooth)?;
         }
 
         Ok(())
@@ -1,130 +1,204 @@
 use std::{
     cell::{Cell, RefCell},
-    fmt::Display,
+    fmt::{self, Display, Formatter, Write as _},
+    str::FromStr,
+    time::{Duration, Instant},
 };
 
 use crate::{
     emojis::AnimatedEmoji,
-    read_line::{read_line_from_path, ReadLineError},
-    Metric,
+    history::History,
+    icons::{Cat, Icons},
+    procfs::{stat::StatError, Stat},
+    sparkline::{self, Sparkline},
+    Metric, Value,
 };
 
-mod emojis {
-    #![allow(dead_code)]
-
-    const fn range_chars<const N: usize>(base: char) -> [char; N] {
-        let mut r = ['0'; N];
-        let mut i = 0;
-        while i < N {
-            r[i] = match core::char::from_u32(base as u32 + i as u32) {
-                Some(c) => c,
-                None => panic!(), // compile time
-            };
-            i += 1;
-        }
-        r
-    }
-
-    pub const SLEEPING_CAT_OLD: [char; 15] = range_chars('\u{e000}');
-    pub const RUNNING_CAT_OLD: [char; 5] = range_chars('\u{e100}');
-    pub const SLEEPING_CAT_NEW: [char; 15] = range_chars('\u{e200}');
-    pub const RUNNING_CAT_NEW: [char; 16] = range_chars('\u{e300}');
-}
-
 const SLEEPING_THRESHOLD_PERCENTAGE: f64 = 0.1;
 
 const MAX_FREQUENCY: f64 = 7.6;
 const MIN_FREQUENCY: f64 = 0.5;
 
+/// How the usage of single cores is shown next to the total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cores {
+    /// A block per core, like a sparkline across cores.
+    Bars,
+    /// The busiest core and its usage.
+    Hottest,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Expected `bars` or `hottest`")]
+pub struct ParseCoresError;
+
+impl FromStr for Cores {
+    type Err = ParseCoresError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bars" => Ok(Cores::Bars),
+            "hottest" => Ok(Cores::Hottest),
+            _ => Err(ParseCoresError),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct CpuMetric {
     cpu_usage: Cell<Option<u8>>,
-    total: Cell<u64>,
-    idle: Cell<u64>,
-    running_cat_emoji: RefCell<AnimatedEmoji<'static>>,
-    sleeping_cat_emoji: RefCell<AnimatedEmoji<'static>>,
+    /// The last sample, and when it was read.
+    previous: RefCell<Option<(Instant, Stat)>>,
+    /// The `cpuN` index and usage of every online core.
+    core_usage: RefCell<Vec<(u32, u8)>>,
+    procs_running: Cell<u32>,
+    context_switches: Cell<u64>,
+    animated: bool,
+    history: History,
+    sparkline: Option<Sparkline>,
+    cores: Option<Cores>,
+    running_cat_emoji: Emoji,
+    sleeping_cat_emoji: Emoji,
 }
 
-#[derive(thiserror::Error, Debug)]
-pub enum CpuError {
-    #[error("IO error: {0}")]
-    Io(#[from] std::io::Error),
-    #[error("UTF-8 error: {0}")]
-    Utf8(#[from] std::str::Utf8Error),
-    #[error("Error parsing a number: {0}")]
-    ParseInt(#[from] std::num::ParseIntError),
-    #[error("Error reading a line: {0}")]
-    ReadLine(#[from] ReadLineError),
-    #[error("Malformed CPU data")]
-    MalformedCpuData,
-}
+type Emoji = RefCell<AnimatedEmoji<'static>>;
 
 impl Default for CpuMetric {
     fn default() -> Self {
         Self {
             cpu_usage: Default::default(),
-            total: Cell::new(1),
-            idle: Cell::new(1),
-            running_cat_emoji: RefCell::new(
-                AnimatedEmoji::builder()
-                    .frames(emojis::RUNNING_CAT_NEW.as_slice())
-                    .min_frequency(MIN_FREQUENCY)
-                    .max_frequency(MAX_FREQUENCY)
-                    .build(),
-            ),
-            sleeping_cat_emoji: RefCell::new(
-                AnimatedEmoji::builder()
-                    .frames(emojis::SLEEPING_CAT_OLD.as_slice())
-                    .min_frequency(0.2)
-                    .max_frequency(0.9)
-                    .build(),
-            ),
+            previous: RefCell::default(),
+            core_usage: RefCell::default(),
+            procs_running: Cell::new(0),
+            context_switches: Cell::new(0),
+            animated: true,
+            history: History::default(),
+            sparkline: None,
+            cores: None,
+            running_cat_emoji: running_cat(Cat::default()),
+            sleeping_cat_emoji: sleeping_cat(Cat::default()),
         }
     }
 }
 
+fn running_cat(cat: Cat) -> Emoji {
+    RefCell::new(
+        AnimatedEmoji::builder()
+            .frames(cat.running())
+            .min_frequency(MIN_FREQUENCY)
+            .max_frequency(MAX_FREQUENCY)
+            .build(),
+    )
+}
+
+fn sleeping_cat(cat: Cat) -> Emoji {
+    RefCell::new(
+        AnimatedEmoji::builder()
+            .frames(cat.sleeping())
+            .min_frequency(0.2)
+            .max_frequency(0.9)
+            .build(),
+    )
+}
+
 impl CpuMetric {
-    fn get_emoji(&self, cpu_usage: u8) -> char {
-        let cpu_usage = cpu_usage as f64 / 100.0;
-        let threshold = SLEEPING_THRESHOLD_PERCENTAGE;
+    /// Shows a still cat instead of the animation, for outputs that refresh
+    /// too rarely for it.
+    #[must_use]
+    pub fn without_animation(self) -> Self {
+        Self { animated: false, ..self }
+    }
 
-        if cpu_usage < threshold {
-            let speed = cpu_usage / threshold;
-            self.running_cat_emoji.borrow_mut().reset();
-            self.sleeping_cat_emoji.borrow_mut().next_frame(speed)
-        } else {
-            let speed = (cpu_usage - threshold) / (1. - threshold);
-            self.sleeping_cat_emoji.borrow_mut().reset();
-            self.running_cat_emoji.borrow_mut().next_frame(speed)
+    /// Draws the cat of `icons`.
+    #[must_use]
+    pub fn with_icons(self, icons: &Icons) -> Self {
+        Self {
+            running_cat_emoji: running_cat(icons.cat),
+            sleeping_cat_emoji: sleeping_cat(icons.cat),
+            ..self
         }
     }
 
-    async fn read_percentage(&self) -> Result<u8, CpuError> {
-        let timings = read_line_from_path::<256>("/proc/stat").await?;
+    /// Draws the recent usage next to the cat.
+    #[must_use]
+    pub fn with_sparkline(self, sparkline: Sparkline) -> Self {
+        Self { sparkline: Some(sparkline), ..self }
+    }
 
-        let mut timings = timings.split_whitespace().skip(1).map(|s| s.parse().unwrap_or(0));
+    /// Shows the usage of single cores as well.
+    #[must_use]
+    pub fn with_cores(self, cores: Cores) -> Self {
+        Self { cores: Some(cores), ..self }
+    }
 
-        let user = timings.next().ok_or(CpuError::MalformedCpuData)?;
-        let nice = timings.next().ok_or(CpuError::MalformedCpuData)?;
-        let system = timings.next().ok_or(CpuError::MalformedCpuData)?;
-        let idle_ = timings.next().ok_or(CpuError::MalformedCpuData)?;
-        let iowait = timings.next().ok_or(CpuError::MalformedCpuData)?;
+    /// The `cpuN` index and usage of every online core, in the order of
+    /// `/proc/stat`.
+    #[must_use]
+    pub fn core_usage(&self) -> std::cell::Ref<'_, [(u32, u8)]> {
+        std::cell::Ref::map(self.core_usage.borrow(), Vec::as_slice)
+    }
 
-        let total_new: u64 = user + nice + system + idle_ + iowait;
-        let idle_new = idle_ + iowait;
+    /// The `cpuN` index of the busiest core, and its usage.
+    fn hottest(&self) -> Option<(u32, u8)> {
+        let cores = self.core_usage.borrow();
+        cores.iter().copied().max_by_key(|&(_, usage)| usage)
+    }
 
-        let delta_total = total_new.saturating_sub(self.total.get());
-        let delta_idle = idle_new.saturating_sub(self.idle.get());
-        let perc_u64 = (delta_total.saturating_sub(delta_idle) * 100)
-            .checked_div(delta_total)
-            .unwrap_or(0);
+    /// Samples `/proc/stat` and returns the total usage since the last sample.
+    fn sample(&self, stat: Stat) -> u8 {
+        let now = Instant::now();
+        let previous = self.previous.take();
+        let (since, earlier) = match &previous {
+            Some((since, earlier)) => (Some(*since), earlier),
+            // Since boot.
+            None => (None, &Stat::default()),
+        };
 
-        let percentage = u8::try_from(perc_u64).unwrap_or(100);
+        let mut cores = self.core_usage.borrow_mut();
+        cores.clear();
+        for core in &stat.cores {
+            // CPUs going on or offline shift the lines.
+            let earlier = earlier.cores.iter().find(|earlier| earlier.index == core.index);
+            let earlier = earlier.map(|earlier| earlier.times).unwrap_or_default();
+            cores.push((core.index, core.times.usage_since(&earlier).unwrap_or(0)));
+        }
 
-        self.total.set(total_new);
-        self.idle.set(idle_new);
+        let switches = stat.context_switches.saturating_sub(earlier.context_switches);
+        let elapsed = since.map_or(0., |since| (now - since).as_secs_f64());
+        self.context_switches.set(if elapsed > 0. {
+            (switches as f64 / elapsed) as u64
+        } else {
+            0
+        });
+        self.procs_running.set(stat.procs_running);
 
-        Ok(percentage)
+        let usage = stat.cpu.usage_since(&earlier.cpu).unwrap_or(0);
+        *self.previous.borrow_mut() = Some((now, stat));
+        usage
+    }
+
+    fn get_emoji(&self, cpu_usage: u8) -> char {
+        let (emoji, other, speed) = self.animation(cpu_usage as f64 / 100.0);
+
+        if !self.animated {
+            return emoji.borrow().current_frame();
+        }
+
+        other.borrow_mut().reset();
+        emoji.borrow_mut().next_frame(speed)
+    }
+
+    /// The cat to play for `cpu_usage` (as a fraction), the other one and the
+    /// speed to play it at.
+    fn animation(&self, cpu_usage: f64) -> (&Emoji, &Emoji, f64) {
+        let threshold = SLEEPING_THRESHOLD_PERCENTAGE;
+
+        if cpu_usage < threshold {
+            (&self.sleeping_cat_emoji, &self.running_cat_emoji, cpu_usage / threshold)
+        } else {
+            let speed = (cpu_usage - threshold) / (1. - threshold);
+            (&self.running_cat_emoji, &self.sleeping_cat_emoji, speed)
+        }
     }
 }
 
@@ -137,25 +211,113 @@ impl Metric for CpuMetric {
         self
     }
 
-    async fn update(&self) -> Result<(), CpuError> {
-        let percentage = self.read_percentage().await?;
+    async fn update(&self) -> Result<(), StatError> {
+        let percentage = self.sample(Stat::read().await?);
 
         self.cpu_usage.set(Some(percentage));
+        self.history.push(percentage as f64);
+
+        Ok(())
+    }
+
+    fn tooltip(&self) -> impl Display {
+        CpuTooltip(self)
+    }
+
+    fn percentage(&self) -> Option<u8> {
+        self.cpu_usage.get()
+    }
+
+    fn history(&self) -> Option<&History> {
+        Some(&self.history)
+    }
+
+    fn value(&self) -> Option<Value> {
+        self.cpu_usage.get().map(Value::Percent)
+    }
+
+    fn frame_period(&self) -> Option<Duration> {
+        let cpu_usage = self.cpu_usage.get().filter(|_| self.animated)?;
+        let (emoji, _, speed) = self.animation(cpu_usage as f64 / 100.);
+        Some(emoji.borrow().until_next_frame(speed))
+    }
 
+    fn animation_frames(&self) -> Option<(char, char)> {
+        let cpu_usage = self.cpu_usage.get().filter(|_| self.animated)?;
+        let (emoji, _, _) = self.animation(cpu_usage as f64 / 100.);
+        let emoji = emoji.borrow();
+        Some((emoji.current_frame(), emoji.peek_next_frame()))
+    }
+
+    fn fields(&self) -> &'static [&'static str] {
+        &["icon", "percent", "graph", "cores", "hottest"]
+    }
+
+    fn write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        let cpu_usage = self.cpu_usage.get()?;
+        Some(match name {
+            "icon" => out.write_char(self.get_emoji(cpu_usage)),
+            "percent" => write!(out, "{cpu_usage}"),
+            "graph" => match &self.sparkline {
+                Some(sparkline) => write!(out, "{}", sparkline.render(&self.history)),
+                None => Ok(()),
+            },
+            "cores" => write!(out, "{}", CoreBars(&self.core_usage.borrow())),
+            "hottest" => write!(out, "{}", self.hottest().map_or(0, |(_, usage)| usage)),
+            _ => return None,
+        })
+    }
+}
+
+struct CpuTooltip<'a>(&'a CpuMetric);
+
+impl Display for CpuTooltip<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Some(cpu_usage) = self.0.cpu_usage.get() else {
+            return write!(f, "CPU usage: unknown");
+        };
+        write!(f, "CPU usage: {cpu_usage}%")?;
+        if let Some((core, usage)) = self.0.hottest() {
+            write!(f, ", busiest core cpu{core} at {usage}%")?;
+        }
+        write!(
+            f,
+            ", {} running, {} context switches/s",
+            self.0.procs_running.get(),
+            self.0.context_switches.get()
+        )
+    }
+}
+
+/// A block per core.
+struct CoreBars<'a>(&'a [(u32, u8)]);
+
+impl Display for CoreBars<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for &(_, usage) in self.0 {
+            f.write_char(sparkline::block(f64::from(usage) / 100.))?;
+        }
         Ok(())
     }
 }
 
 impl Display for CpuMetric {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let cpu_usage = self.cpu_usage.get();
-
-        if let Some(cpu_usage) = cpu_usage {
-            let emoji = self.get_emoji(cpu_usage);
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Some(cpu_usage) = self.cpu_usage.get() else {
+            return Ok(());
+        };
 
-            write!(f, "{emoji} {cpu_usage: >2}% cpu")
-        } else {
-            Ok(())
+        write!(f, "{}", self.get_emoji(cpu_usage))?;
+        if let Some(sparkline) = &self.sparkline {
+            write!(f, " {}", sparkline.render(&self.history))?;
+        }
+        if self.cores == Some(Cores::Bars) {
+            write!(f, " {}", CoreBars(&self.core_usage.borrow()))?;
         }
+        write!(f, " {cpu_usage: >2}% cpu")?;
+        if let (Some(Cores::Hottest), Some((core, usage))) = (self.cores, self.hottest()) {
+            write!(f, " (cpu{core} {usage}%)")?;
+        }
+        Ok(())
     }
 }
@@ -1,6 +1,9 @@
-use crate::Metric;
-use chrono::offset::Local;
-use std::fmt::{Display, Formatter};
+use crate::{Metric, Value};
+use chrono::{offset::Local, Timelike};
+use std::{
+    fmt::{Display, Formatter},
+    time::Duration,
+};
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct DateMetric;
@@ -17,6 +20,15 @@ impl Metric for DateMetric {
     async fn update(&self) -> Result<(), !> {
         Ok(())
     }
+
+    fn value(&self) -> Option<Value> {
+        Value::text(self)
+    }
+
+    fn frame_period(&self) -> Option<Duration> {
+        let nanos = Local::now().nanosecond() % 1_000_000_000;
+        Some(Duration::from_nanos(u64::from(1_000_000_000 - nanos)))
+    }
 }
 
 impl Display for DateMetric {
@@ -0,0 +1,261 @@
+use std::{
+    cell::RefCell,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use crate::{
+    history::History,
+    icons::Icons,
+    read_line::{read_line_from_path, ReadLineError},
+    CommonError, Metric, Value,
+};
+
+const CPU_DIR: &str = "/sys/devices/system/cpu";
+
+type Name = heapless::String<32>;
+
+/// Which frequency of the cores is shown.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    #[default]
+    Average,
+    Max,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Expected `average` or `max`")]
+pub struct ParseAggregateError;
+
+impl FromStr for Aggregate {
+    type Err = ParseAggregateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "average" => Ok(Aggregate::Average),
+            "max" => Ok(Aggregate::Max),
+            _ => Err(ParseAggregateError),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Sample {
+    /// In kHz, as the kernel reports it.
+    average: u64,
+    max: u64,
+    cores: usize,
+    governor: Name,
+    /// Energy performance preference, only offered by some drivers.
+    preference: Option<Name>,
+}
+
+impl Sample {
+    /// Whether the CPU is set up for speed (`Some(true)`) or for battery life
+    /// (`Some(false)`). The preference wins over the governor: `intel_pstate`
+    /// and `amd-pstate` run `powersave` by default and tune with it instead.
+    fn performance(&self) -> Option<bool> {
+        match self.preference.as_deref() {
+            Some("performance") => Some(true),
+            Some("power" | "balance_power") => Some(false),
+            Some(_) => None,
+            None => match self.governor.as_str() {
+                "performance" => Some(true),
+                "powersave" => Some(false),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Current frequency of the cores from `cpufreq`, with the governor.
+#[derive(Debug, Default)]
+pub struct FrequencyMetric {
+    sample: RefCell<Option<Sample>>,
+    aggregate: Aggregate,
+    icons: Icons,
+    /// Shown frequency, in MHz.
+    history: History,
+}
+
+impl FrequencyMetric {
+    /// Shows the `aggregate` frequency of the cores instead of the average.
+    #[must_use]
+    pub fn with_aggregate(self, aggregate: Aggregate) -> Self {
+        Self { aggregate, ..self }
+    }
+
+    /// Shows the governor icons of `icons`.
+    #[must_use]
+    pub fn with_icons(self, icons: &Icons) -> Self {
+        Self { icons: icons.clone(), ..self }
+    }
+
+    /// Shown frequency, in kHz.
+    fn khz(&self) -> Option<u64> {
+        let sample = self.sample.borrow();
+        let sample = sample.as_ref()?;
+        Some(match self.aggregate {
+            Aggregate::Average => sample.average,
+            Aggregate::Max => sample.max,
+        })
+    }
+
+    fn icon(&self) -> &str {
+        match self.sample.borrow().as_ref().and_then(Sample::performance) {
+            Some(true) => &self.icons.performance,
+            Some(false) => &self.icons.powersave,
+            None => "",
+        }
+    }
+
+    /// Reads the `cpufreq` directory of every core. `None` without cpufreq,
+    /// e.g. in most virtual machines.
+    async fn read() -> Result<Option<Sample>, CommonError> {
+        let mut entries = tokio::fs::read_dir(CPU_DIR).await?;
+        let (mut sum, mut sample) = (0, Sample::default());
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            let is_core =
+                name.to_str().and_then(|name| name.strip_prefix("cpu")).is_some_and(|number| {
+                    !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit())
+                });
+            if !is_core {
+                continue;
+            }
+
+            let cpufreq = entry.path().join("cpufreq");
+            let khz = match read_line_from_path::<24>(cpufreq.join("scaling_cur_freq")).await {
+                Ok(khz) => khz.trim().parse::<u64>()?,
+                // Offline, or no cpufreq driver.
+                Err(ReadLineError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => {
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
+            sum += khz;
+            sample.max = sample.max.max(khz);
+            sample.cores += 1;
+
+            if sample.cores == 1 {
+                // Both are only shown, a driver without them is no reason to fail.
+                let governor = read_line_from_path::<32>(cpufreq.join("scaling_governor")).await;
+                sample.governor = governor
+                    .ok()
+                    .and_then(|governor| Name::try_from(governor.trim()).ok())
+                    .unwrap_or_default();
+                let preference = cpufreq.join("energy_performance_preference");
+                sample.preference = read_line_from_path::<32>(preference)
+                    .await
+                    .ok()
+                    .and_then(|preference| Name::try_from(preference.trim()).ok());
+            }
+        }
+
+        if sample.cores == 0 {
+            return Ok(None);
+        }
+        sample.average = sum / sample.cores as u64;
+        Ok(Some(sample))
+    }
+}
+
+/// `khz` in GHz with one decimal.
+struct Ghz(u64);
+
+impl Display for Ghz {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let tenths = (self.0 + 50_000) / 100_000;
+        write!(f, "{}.{}", tenths / 10, tenths % 10)
+    }
+}
+
+impl Metric for FrequencyMetric {
+    fn name(&self) -> &'static str {
+        "Frequency"
+    }
+
+    fn display(&self) -> impl Display {
+        self
+    }
+
+    async fn update(&self) -> Result<(), CommonError> {
+        match Self::read().await {
+            Ok(sample) => {
+                *self.sample.borrow_mut() = sample;
+                if let Some(khz) = self.khz() {
+                    self.history.push(khz as f64 / 1000.);
+                }
+                Ok(())
+            }
+            Err(err) => {
+                *self.sample.borrow_mut() = None;
+                Err(err)
+            }
+        }
+    }
+
+    fn tooltip(&self) -> impl Display {
+        FrequencyTooltip(self)
+    }
+
+    fn history(&self) -> Option<&History> {
+        Some(&self.history)
+    }
+
+    fn value(&self) -> Option<Value> {
+        self.khz().map(Value::Frequency)
+    }
+
+    fn fields(&self) -> &'static [&'static str] {
+        &["icon", "ghz", "governor", "preference"]
+    }
+
+    fn write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        let khz = self.khz()?;
+        let sample = self.sample.borrow();
+        let sample = sample.as_ref()?;
+        Some(match name {
+            "icon" => out.write_str(self.icon()),
+            "ghz" => write!(out, "{}", Ghz(khz)),
+            "governor" => out.write_str(&sample.governor),
+            "preference" => out.write_str(sample.preference.as_deref().unwrap_or_default()),
+            _ => return None,
+        })
+    }
+}
+
+struct FrequencyTooltip<'a>(&'a FrequencyMetric);
+
+impl Display for FrequencyTooltip<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let sample = self.0.sample.borrow();
+        let Some(sample) = sample.as_ref() else {
+            return write!(f, "CPU frequency: unknown");
+        };
+        write!(
+            f,
+            "CPU frequency: {} GHz average, {} GHz max over {} cores, governor {}",
+            Ghz(sample.average),
+            Ghz(sample.max),
+            sample.cores,
+            sample.governor
+        )?;
+        if let Some(preference) = &sample.preference {
+            write!(f, ", preference {preference}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for FrequencyMetric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Some(khz) = self.khz() else {
+            return Ok(());
+        };
+        match self.icon() {
+            "" => write!(f, "{}GHz", Ghz(khz)),
+            icon => write!(f, "{icon} {}GHz", Ghz(khz)),
+        }
+    }
+}
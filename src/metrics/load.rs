@@ -0,0 +1,222 @@
+use std::{
+    cell::Cell,
+    fmt::{self, Display, Formatter},
+    num::NonZeroUsize,
+    str::FromStr,
+};
+
+use crate::{
+    history::History,
+    icons::Icons,
+    procfs::{
+        loadavg::{LoadAvg, LoadAvgError},
+        stat::StatError,
+        Stat,
+    },
+    Level, Metric, Value,
+};
+
+/// Load per core the icon turns busy at by default.
+pub const WARNING_LOAD: f32 = 1.;
+/// Load per core the icon turns overloaded at by default.
+pub const CRITICAL_LOAD: f32 = 2.;
+
+/// How the load is shown.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LoadDisplay {
+    /// The 1-minute load per core.
+    #[default]
+    Number,
+    /// Only the icon of the level.
+    Icon,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Expected `number` or `icon`")]
+pub struct ParseLoadDisplayError;
+
+impl FromStr for LoadDisplay {
+    type Err = ParseLoadDisplayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "number" => Ok(LoadDisplay::Number),
+            "icon" => Ok(LoadDisplay::Icon),
+            _ => Err(ParseLoadDisplayError),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum LoadError {
+    #[error("Error reading /proc/loadavg: {0}")]
+    LoadAvg(#[from] LoadAvgError),
+    #[error("Error reading /proc/stat: {0}")]
+    Stat(#[from] StatError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sample {
+    load: LoadAvg,
+    /// Online CPUs, which the load counts tasks of.
+    cores: NonZeroUsize,
+}
+
+impl Sample {
+    async fn read() -> Result<Self, LoadError> {
+        let load = LoadAvg::read().await?;
+        let cores = Stat::read().await?.cores.len();
+        let cores = NonZeroUsize::new(cores).ok_or(StatError::Missing("cpuN"))?;
+        Ok(Self { load, cores })
+    }
+
+    /// The 1-minute load per core.
+    fn normalized(&self) -> f32 {
+        self.load.one / self.cores.get() as f32
+    }
+}
+
+/// Load average from `/proc/loadavg`, per core so that `1.00` is a fully busy
+/// machine whatever its size.
+#[derive(Debug)]
+pub struct LoadMetric {
+    sample: Cell<Option<Sample>>,
+    display: LoadDisplay,
+    warning: f32,
+    critical: f32,
+    icons: Icons,
+    /// Load per core, in percent.
+    history: History,
+}
+
+impl Default for LoadMetric {
+    fn default() -> Self {
+        Self {
+            sample: Cell::default(),
+            display: LoadDisplay::default(),
+            warning: WARNING_LOAD,
+            critical: CRITICAL_LOAD,
+            icons: Icons::default(),
+            history: History::default(),
+        }
+    }
+}
+
+impl LoadMetric {
+    #[must_use]
+    pub fn with_display(self, display: LoadDisplay) -> Self {
+        Self { display, ..self }
+    }
+
+    /// Raises the level, and changes the icon, once the load per core reaches
+    /// `warning` or `critical`.
+    #[must_use]
+    pub fn with_thresholds(self, warning: f32, critical: f32) -> Self {
+        Self { warning, critical, ..self }
+    }
+
+    /// Shows the load icons of `icons`.
+    #[must_use]
+    pub fn with_icons(self, icons: &Icons) -> Self {
+        Self { icons: icons.clone(), ..self }
+    }
+
+    fn icon(&self) -> &str {
+        match self.level() {
+            Level::Normal => &self.icons.load,
+            Level::Warning => &self.icons.busy,
+            Level::Critical => &self.icons.overloaded,
+        }
+    }
+}
+
+impl Metric for LoadMetric {
+    fn name(&self) -> &'static str {
+        "Load"
+    }
+
+    fn display(&self) -> impl Display {
+        self
+    }
+
+    async fn update(&self) -> Result<(), LoadError> {
+        match Sample::read().await {
+            Ok(sample) => {
+                self.sample.set(Some(sample));
+                self.history.push(f64::from(sample.normalized()) * 100.);
+                Ok(())
+            }
+            Err(err) => {
+                self.sample.set(None);
+                Err(err)
+            }
+        }
+    }
+
+    fn tooltip(&self) -> impl Display {
+        LoadTooltip(self.sample.get())
+    }
+
+    fn level(&self) -> Level {
+        match self.sample.get().map(|sample| sample.normalized()) {
+            Some(load) if load >= self.critical => Level::Critical,
+            Some(load) if load >= self.warning => Level::Warning,
+            _ => Level::Normal,
+        }
+    }
+
+    fn history(&self) -> Option<&History> {
+        Some(&self.history)
+    }
+
+    fn value(&self) -> Option<Value> {
+        let load = self.sample.get()?.normalized();
+        Some(Value::Load((load * 100.).round() as u32))
+    }
+
+    fn fields(&self) -> &'static [&'static str] {
+        &["icon", "load", "one", "five", "fifteen", "running", "total"]
+    }
+
+    fn write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        let sample = self.sample.get()?;
+        let load = sample.load;
+        Some(match name {
+            "icon" => out.write_str(self.icon()),
+            "load" => write!(out, "{:.2}", sample.normalized()),
+            "one" => write!(out, "{:.2}", load.one),
+            "five" => write!(out, "{:.2}", load.five),
+            "fifteen" => write!(out, "{:.2}", load.fifteen),
+            "running" => write!(out, "{}", load.running),
+            "total" => write!(out, "{}", load.total),
+            _ => return None,
+        })
+    }
+}
+
+struct LoadTooltip(Option<Sample>);
+
+impl Display for LoadTooltip {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Some(Sample { load, cores }) = self.0 else {
+            return write!(f, "Load: unknown");
+        };
+        write!(
+            f,
+            "Load: {:.2} {:.2} {:.2} over {cores} cores, {} of {} tasks running",
+            load.one, load.five, load.fifteen, load.running, load.total
+        )
+    }
+}
+
+impl Display for LoadMetric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Some(sample) = self.sample.get() else {
+            return Ok(());
+        };
+        match self.display {
+            LoadDisplay::Number => write!(f, "{} {:.2}", self.icon(), sample.normalized()),
+            LoadDisplay::Icon => f.write_str(self.icon()),
+        }
+    }
+}
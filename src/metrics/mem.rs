@@ -1,12 +1,150 @@
-use crate::{CommonError, Metric};
-use std::{cell::Cell, fmt::Display};
-use tokio::process::Command;
+use std::{
+    cell::Cell,
+    fmt::{self, Display, Formatter},
+    io::ErrorKind,
+    str::FromStr,
+};
 
-type Usage = heapless::String<24>;
+use crate::{
+    history::History,
+    icons::Icons,
+    metrics::net::NumFmt,
+    procfs::meminfo::{MemInfo, MemInfoError},
+    sparkline::Sparkline,
+    Level, Metric, Value,
+};
 
-#[derive(Default)]
+const BLOCK_DIR: &str = "/sys/block";
+
+const WARNING_PERCENTAGE: u8 = 80;
+const CRITICAL_PERCENTAGE: u8 = 95;
+
+/// How the memory is shown.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MemDisplay {
+    /// `3.2GiB/15.5GiB`.
+    #[default]
+    Used,
+    /// `21%` used.
+    Percent,
+    /// `12.3GiB` available.
+    Available,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Expected `used`, `percent` or `available`")]
+pub struct ParseMemDisplayError;
+
+impl FromStr for MemDisplay {
+    type Err = ParseMemDisplayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "used" => Ok(MemDisplay::Used),
+            "percent" => Ok(MemDisplay::Percent),
+            "available" => Ok(MemDisplay::Available),
+            _ => Err(ParseMemDisplayError),
+        }
+    }
+}
+
+/// All zram devices together, in bytes, from their `mm_stat`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Zram {
+    /// Data stored, uncompressed.
+    original: u64,
+    compressed: u64,
+    /// Memory the devices take, allocator overhead included.
+    used: u64,
+}
+
+impl Zram {
+    /// Sums the `mm_stat` of every `zram*` block device, `None` without any.
+    async fn read() -> Result<Option<Self>, MemInfoError> {
+        let mut entries = match tokio::fs::read_dir(BLOCK_DIR).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let mut zram = None::<Zram>;
+        while let Some(entry) = entries.next_entry().await? {
+            if !entry.file_name().to_str().is_some_and(|name| name.starts_with("zram")) {
+                continue;
+            }
+            let mm_stat = match tokio::fs::read_to_string(entry.path().join("mm_stat")).await {
+                Ok(mm_stat) => mm_stat,
+                // Not initialized yet.
+                Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            let mut fields = mm_stat.split_whitespace().map(str::parse::<u64>);
+            let mut next = || fields.next().unwrap_or(Ok(0));
+            let (original, compressed, used) = (next()?, next()?, next()?);
+            let total = zram.get_or_insert_default();
+            total.original += original;
+            total.compressed += compressed;
+            total.used += used;
+        }
+        Ok(zram)
+    }
+
+    /// How many times smaller the data is compressed, in tenths.
+    fn ratio(&self) -> Option<u64> {
+        (self.compressed > 0).then(|| self.original * 10 / self.compressed)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Sample {
+    info: MemInfo,
+    zram: Option<Zram>,
+}
+
+impl Sample {
+    fn percent(&self) -> u8 {
+        match self.info.total {
+            0 => 0,
+            total => (self.info.used() * 100 / total) as u8,
+        }
+    }
+}
+
+/// Memory in use from `/proc/meminfo`, with swap and zram in the tooltip.
+#[derive(Debug, Default)]
 pub struct MemMetric {
-    usage: Cell<Usage>,
+    sample: Cell<Option<Sample>>,
+    display: MemDisplay,
+    icons: Icons,
+    history: History,
+    sparkline: Option<Sparkline>,
+}
+
+impl MemMetric {
+    #[must_use]
+    pub fn with_display(self, display: MemDisplay) -> Self {
+        Self { display, ..self }
+    }
+
+    /// Shows the memory icon of `icons`.
+    #[must_use]
+    pub fn with_icons(self, icons: &Icons) -> Self {
+        Self { icons: icons.clone(), ..self }
+    }
+
+    /// Draws the recent usage next to the memory.
+    #[must_use]
+    pub fn with_sparkline(self, sparkline: Sparkline) -> Self {
+        Self { sparkline: Some(sparkline), ..self }
+    }
+
+    async fn read() -> Result<Sample, MemInfoError> {
+        Ok(Sample { info: MemInfo::read().await?, zram: Zram::read().await? })
+    }
+}
+
+/// KiB with a binary prefix.
+fn kib(kib: u64) -> NumFmt {
+    NumFmt(kib * 1024)
 }
 
 impl Metric for MemMetric {
@@ -18,38 +156,128 @@ impl Metric for MemMetric {
         self
     }
 
-    async fn update(&self) -> Result<(), CommonError> {
-        match try {
-            // TODO: rewrite from shell api
-            let out = Command::new("sh")
-                .arg("-c")
-                .arg("free -h | awk '/Mem/ {printf \"%s/%s\n\", $3, $2}'")
-                .output()
-                .await?;
-
-            if !out.status.success() {
-                return Err(CommonError::UnsuccessfullShell(out.status));
+    async fn update(&self) -> Result<(), MemInfoError> {
+        match Self::read().await {
+            Ok(sample) => {
+                self.sample.set(Some(sample));
+                self.history.push(f64::from(sample.percent()));
+                Ok(())
             }
-
-            let out = std::str::from_utf8(&out.stdout)?;
-
-            self.usage.set(Usage::try_from(out).map_err(|()| CommonError::Capacity)?);
-        } {
-            Ok(()) => Ok(()),
             Err(err) => {
-                self.usage.set(Default::default());
+                self.sample.set(None);
                 Err(err)
             }
         }
     }
+
+    fn tooltip(&self) -> impl Display {
+        MemTooltip(self.sample.get())
+    }
+
+    fn percentage(&self) -> Option<u8> {
+        self.sample.get().as_ref().map(Sample::percent)
+    }
+
+    fn level(&self) -> Level {
+        match self.percentage() {
+            Some(p) if p >= CRITICAL_PERCENTAGE => Level::Critical,
+            Some(p) if p >= WARNING_PERCENTAGE => Level::Warning,
+            _ => Level::Normal,
+        }
+    }
+
+    fn history(&self) -> Option<&History> {
+        Some(&self.history)
+    }
+
+    fn value(&self) -> Option<Value> {
+        self.percentage().map(Value::Percent)
+    }
+
+    fn fields(&self) -> &'static [&'static str] {
+        &[
+            "icon",
+            "used",
+            "total",
+            "available",
+            "percent",
+            "graph",
+            "shmem",
+            "swap",
+            "swap_total",
+            "zram",
+            "zram_ratio",
+        ]
+    }
+
+    fn write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        let sample = self.sample.get()?;
+        let info = sample.info;
+        Some(match name {
+            "icon" => out.write_str(&self.icons.memory),
+            "used" => write!(out, "{}", kib(info.used())),
+            "total" => write!(out, "{}", kib(info.total)),
+            "available" => write!(out, "{}", kib(info.available)),
+            "percent" => write!(out, "{}", sample.percent()),
+            "graph" => match &self.sparkline {
+                Some(sparkline) => write!(out, "{}", sparkline.render(&self.history)),
+                None => Ok(()),
+            },
+            "shmem" => write!(out, "{}", kib(info.shmem)),
+            "swap" => write!(out, "{}", kib(info.swap_used())),
+            "swap_total" => write!(out, "{}", kib(info.swap_total)),
+            "zram" => write!(out, "{}", NumFmt(sample.zram?.used)),
+            "zram_ratio" => {
+                let ratio = sample.zram?.ratio()?;
+                write!(out, "{}.{}", ratio / 10, ratio % 10)
+            }
+            _ => return None,
+        })
+    }
 }
 
-impl Display for MemMetric {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let usage = self.usage.take();
+struct MemTooltip(Option<Sample>);
 
-        self.usage.set(usage.clone());
+impl Display for MemTooltip {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Some(Sample { info, zram }) = self.0 else {
+            return write!(f, "Memory: unknown");
+        };
+        write!(
+            f,
+            "Memory: {} of {} used, {} available, {} shared",
+            kib(info.used()),
+            kib(info.total),
+            kib(info.available),
+            kib(info.shmem)
+        )?;
+        if info.swap_total > 0 {
+            write!(f, "; swap: {} of {}", kib(info.swap_used()), kib(info.swap_total))?;
+        }
+        if let Some(zram) = zram {
+            write!(f, "; zram: {} stored in {}", NumFmt(zram.original), NumFmt(zram.used))?;
+            if let Some(ratio) = zram.ratio() {
+                write!(f, " ({}.{}x)", ratio / 10, ratio % 10)?;
+            }
+        }
+        Ok(())
+    }
+}
 
-        write!(f, "📝 {usage}")
+impl Display for MemMetric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Some(sample) = self.sample.get() else {
+            return Ok(());
+        };
+        let (icon, info) = (&self.icons.memory, sample.info);
+        match self.display {
+            MemDisplay::Used => write!(f, "{icon} {}/{}", kib(info.used()), kib(info.total)),
+            MemDisplay::Percent => write!(f, "{icon} {}%", sample.percent()),
+            MemDisplay::Available => write!(f, "{icon} {}", kib(info.available)),
+        }?;
+        if let Some(sparkline) = &self.sparkline {
+            write!(f, " {}", sparkline.render(&self.history))?;
+        }
+        Ok(())
     }
 }
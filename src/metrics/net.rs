@@ -1,21 +1,250 @@
 use std::{
-    cell::Cell,
-    fmt::Display,
-    path::Path,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    convert::Infallible,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
     time::{Duration, Instant},
 };
 
-use tokio::process::Command;
+use crate::{
+    history::History,
+    icons::Icons,
+    nl80211,
+    procfs::{DefaultRoute, Wireless},
+    sparkline::Sparkline,
+    sysfs::net::{interfaces, Interface, Name},
+    CommonError, Metric, Value,
+};
+
+/// Names skipped unless configured otherwise: container and VM plumbing, whose
+/// traffic the physical interfaces count too.
+pub const DEFAULT_EXCLUDE: &str = "lo docker* veth* br-* virbr*";
+
+/// Interface names, where `*` matches any run of characters. Separated by
+/// spaces or commas.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Patterns(Vec<String>);
+
+impl Patterns {
+    #[must_use]
+    pub fn matches(&self, name: &str) -> bool {
+        self.0.iter().any(|pattern| glob(pattern, name))
+    }
+}
 
-use crate::{read_line::read_line_from_path, CommonError, Metric};
+impl FromStr for Patterns {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let patterns = s.split([',', ' ']).filter(|pattern| !pattern.is_empty());
+        Ok(Self(patterns.map(str::to_owned).collect()))
+    }
+}
+
+fn glob(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(name) = name.strip_prefix(prefix) else {
+                return false;
+            };
+            // `*` takes as much as needed for the rest to match.
+            (0..=name.len())
+                .filter(|&skip| name.is_char_boundary(skip))
+                .any(|skip| glob(rest, &name[skip..]))
+        }
+    }
+}
+
+/// Which of the interfaces that are up the totals count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceFilter {
+    /// Only these if set. Otherwise every interface but loopbacks and bridges,
+    /// which count traffic of other interfaces.
+    pub include: Option<Patterns>,
+    pub exclude: Patterns,
+}
+
+impl Default for InterfaceFilter {
+    fn default() -> Self {
+        let Ok(exclude) = DEFAULT_EXCLUDE.parse();
+        Self { include: None, exclude }
+    }
+}
+
+impl InterfaceFilter {
+    /// Whether the patterns allow an interface called `name`, so that excluded
+    /// interfaces are not even read.
+    #[must_use]
+    pub fn allows_name(&self, name: &str) -> bool {
+        !self.exclude.matches(name)
+            && self.include.as_ref().is_none_or(|include| include.matches(name))
+    }
+
+    #[must_use]
+    pub fn allows(&self, interface: &Interface) -> bool {
+        self.allows_name(&interface.name)
+            && (self.include.is_some() || !interface.is_loopback() && !interface.bridge)
+    }
+}
+
+/// What kind of link the active interface is.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Connection {
+    /// No counted interface has a default route.
+    #[default]
+    Offline,
+    Wired,
+    Wifi,
+    Tethering,
+}
+
+impl Connection {
+    fn of(interface: &Interface) -> Self {
+        if interface.tethered {
+            Connection::Tethering
+        } else if interface.wireless {
+            Connection::Wifi
+        } else {
+            Connection::Wired
+        }
+    }
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Connection::Offline => "offline",
+            Connection::Wired => "wired",
+            Connection::Wifi => "wifi",
+            Connection::Tethering => "tethering",
+        }
+    }
+
+    #[must_use]
+    pub fn icon(self, icons: &Icons) -> &str {
+        match self {
+            Connection::Offline => &icons.offline,
+            Connection::Wired => &icons.wired,
+            Connection::Wifi => &icons.wifi,
+            Connection::Tethering => &icons.tethering,
+        }
+    }
+}
+
+/// The counted interface with the preferred default route, the one new
+/// connections go through.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Active {
+    name: Name,
+    connection: Connection,
+    /// Only for Wi-Fi, while associated.
+    ssid: Option<String>,
+    /// Link quality percentage, only for Wi-Fi.
+    signal: Option<u8>,
+}
+
+/// What [`Active::find`] failed to read at the last update. A lasting failure
+/// is logged as a warning once, then at debug level until it recovers.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Failing {
+    routes: Cell<bool>,
+    wireless: Cell<bool>,
+    ssid: Cell<bool>,
+}
+
+/// The level to log a failure at, given whether it failed last time too.
+fn failure_level(failing: &Cell<bool>) -> log::Level {
+    if failing.replace(true) {
+        log::Level::Debug
+    } else {
+        log::Level::Warn
+    }
+}
+
+impl Active {
+    /// The first of `counted` that a default route goes through, with the SSID
+    /// and the signal of Wi-Fi interfaces.
+    async fn find(counted: &[Interface], failing: &Failing) -> Option<Self> {
+        let routes = match DefaultRoute::read().await {
+            Ok(routes) => {
+                failing.routes.set(false);
+                routes
+            }
+            Err(err) => {
+                let level = failure_level(&failing.routes);
+                log::log!(level, "Error reading the default routes: {err}");
+                return None;
+            }
+        };
+        let interface = routes
+            .iter()
+            .find_map(|route| counted.iter().find(|interface| interface.name == route.interface))?;
+        let mut active = Active {
+            name: interface.name.clone(),
+            connection: Connection::of(interface),
+            ssid: None,
+            signal: None,
+        };
+        if interface.wireless {
+            match Wireless::read().await {
+                Ok(wireless) => {
+                    failing.wireless.set(false);
+                    let wireless =
+                        wireless.iter().find(|wireless| wireless.interface == active.name);
+                    active.signal = wireless.map(Wireless::signal);
+                }
+                Err(err) => {
+                    let level = failure_level(&failing.wireless);
+                    log::log!(level, "Error reading the link quality: {err}");
+                }
+            }
+            let index = interface.index;
+            match tokio::task::spawn_blocking(move || nl80211::ssid(index)).await {
+                Ok(Ok(ssid)) => {
+                    failing.ssid.set(false);
+                    active.ssid = ssid;
+                }
+                Ok(Err(err)) => {
+                    let level = failure_level(&failing.ssid);
+                    log::log!(level, "Error asking nl80211 for the SSID of {}: {err}", active.name);
+                }
+                Err(err) => log::warn!("The SSID query panicked or was cancelled: {err}"),
+            }
+        }
+        Some(active)
+    }
+}
+
+/// An interface counted at the last update.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Counters {
+    rx: u64,
+    tx: u64,
+    /// Its own rates, like [`NetMetricInner::rates`].
+    rates: Option<(f64, f64)>,
+}
 
-#[derive(Default, Debug, Clone, PartialEq, Eq)]
-pub struct NetMetric(Cell<NetMetricInner>);
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct NetMetric {
+    inner: Cell<NetMetricInner>,
+    /// Every counted interface at the last update.
+    counters: RefCell<HashMap<Name, Counters>>,
+    active: RefCell<Option<Active>>,
+    failing: Failing,
+    /// Time constant of the moving average of the rates, zero for none.
+    smoothing: Duration,
+    download_history: History,
+    upload_history: History,
+    sparkline: Option<Sparkline>,
+    filter: InterfaceFilter,
+    icons: Icons,
+}
 
-#[derive(Default, Copy, Debug, Clone, PartialEq, Eq)]
+#[derive(Default, Copy, Debug, Clone, PartialEq)]
 struct NetMetricInner {
-    upload: u64,
-    download: u64,
+    /// Download and upload in bytes per second, once two updates happened.
+    rates: Option<(f64, f64)>,
     rx_bytes: u64,
     tx_bytes: u64,
     previous_update: Option<Instant>,
@@ -23,7 +252,8 @@ struct NetMetricInner {
 
 const POWERS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
 
-struct NumFmt(u64);
+/// Bytes with a binary prefix, e.g. `1.50MiB`.
+pub(crate) struct NumFmt(pub u64);
 
 impl Display for NumFmt {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -38,7 +268,7 @@ impl Display for NumFmt {
         let power = POWERS[pow];
 
         if rem > 0 && number < 1000 {
-            write!(f, "{number}.{rem}{power}")
+            write!(f, "{number}.{rem:02}{power}")
         } else {
             write!(f, "{number}{power}")
         }
@@ -46,105 +276,266 @@ impl Display for NumFmt {
 }
 
 impl NetMetric {
-    async fn for_zipped_xfiles<F: async FnMut(&Path, &Path)>(mut f: F) -> Result<(), CommonError> {
-        let out = Command::new("sh")
-            .arg("-c")
-            .arg("ip addr | awk '/state UP/ {print $2}' | sed 's/.$//'")
-            .output()
-            .await?;
-
-        if !out.status.success() {
-            return Err(CommonError::UnsuccessfullShell(out.status));
-        }
+    /// Draws the recent download and upload rates next to the current ones.
+    #[must_use]
+    pub fn with_sparklines(self, sparkline: Sparkline) -> Self {
+        Self { sparkline: Some(sparkline), ..self }
+    }
 
-        let ifs = std::str::from_utf8(&out.stdout)?;
-        let mut ifs = ifs.lines().filter(|iface| !iface.is_empty());
+    /// Smooths the rates with an exponential moving average that forgets
+    /// older rates with time constant `smoothing`, so that bursts do not make
+    /// the bar jump.
+    #[must_use]
+    pub fn with_smoothing(self, smoothing: Duration) -> Self {
+        Self { smoothing, ..self }
+    }
 
-        let paths = core::iter::from_fn(|| {
-            let iface = ifs.next()?;
-            let mut string = heapless::String::<256>::new();
-            _ = string.push_str("/sys/class/net/");
-            _ = string.push_str(iface);
-            _ = string.push_str("/statistics/");
-            let mut rx = string.clone();
-            _ = rx.push_str("rx_bytes");
-            _ = string.push_str("tx_bytes");
+    /// Counts only the interfaces `filter` allows.
+    #[must_use]
+    pub fn with_filter(self, filter: InterfaceFilter) -> Self {
+        Self { filter, ..self }
+    }
 
-            Some((rx, string))
-        });
+    /// Shows the download and upload icons of `icons`.
+    #[must_use]
+    pub fn with_icons(self, icons: &Icons) -> Self {
+        Self { icons: icons.clone(), ..self }
+    }
 
-        for (rx, tx) in paths {
-            f(Path::new(&rx[..]), Path::new(&tx[..])).await;
+    /// Download and upload rates, in bytes per second.
+    #[must_use]
+    pub fn rates(&self) -> (u64, u64) {
+        let (download, upload) = self.inner.get().rates.unwrap_or_default();
+        (download.round() as u64, upload.round() as u64)
+    }
+
+    /// Download and upload rates of the interface called `name`, in bytes per
+    /// second. `None` unless it was counted at the last two updates.
+    #[must_use]
+    pub fn interface_rates(&self, name: &str) -> Option<(u64, u64)> {
+        let name = Name::try_from(name).ok()?;
+        let (download, upload) = self.counters.borrow().get(&name)?.rates?;
+        Some((download.round() as u64, upload.round() as u64))
+    }
+
+    /// What kind of link the interface with the default route is.
+    #[must_use]
+    pub fn connection(&self) -> Connection {
+        self.active
+            .borrow()
+            .as_ref()
+            .map_or(Connection::Offline, |active| active.connection)
+    }
+
+    /// Weight of a rate measured over `elapsed` against the average so far.
+    fn weight(&self, elapsed: Duration) -> f64 {
+        if self.smoothing.is_zero() {
+            1.
+        } else {
+            1. - (-elapsed.as_secs_f64() / self.smoothing.as_secs_f64()).exp()
         }
-        Ok(())
     }
+
+    /// `rx` and `tx` bytes counted over `elapsed`, as rates averaged with
+    /// `average`.
+    fn smooth(
+        &self,
+        average: Option<(f64, f64)>,
+        (rx, tx): (u64, u64),
+        elapsed: Duration,
+    ) -> (f64, f64) {
+        let seconds = elapsed.as_secs_f64();
+        let (download, upload) = (rx as f64 / seconds, tx as f64 / seconds);
+        match average {
+            Some((average_download, average_upload)) => {
+                let weight = self.weight(elapsed);
+                (
+                    average_download + (download - average_download) * weight,
+                    average_upload + (upload - average_upload) * weight,
+                )
+            }
+            None => (download, upload),
+        }
+    }
+
+    /// Recent download rates, in bytes per second.
+    #[must_use]
+    pub fn download_history(&self) -> &History {
+        &self.download_history
+    }
+
+    /// Recent upload rates, in bytes per second.
+    #[must_use]
+    pub fn upload_history(&self) -> &History {
+        &self.upload_history
+    }
+}
+
+/// Bytes counted from `before` to `now`. A counter that went back was reset,
+/// e.g. by a driver that clears its statistics when the link goes down, and
+/// counted from zero since.
+fn since(before: u64, now: u64) -> u64 {
+    now.checked_sub(before).unwrap_or(now)
 }
 
 impl Metric for NetMetric {
     fn display(&self) -> impl Display {
-        self.0.get()
+        self
     }
     fn name(&self) -> &'static str {
         "Net"
     }
 
     async fn update(&self) -> Result<(), CommonError> {
-        let mut inner = self.0.get();
-        let delta = inner.previous_update.map_or(Duration::from_secs(0), |prev| prev.elapsed());
-
-        let mut rx_bytes = 0;
-        let mut tx_bytes = 0;
-
-        Self::for_zipped_xfiles(async |rx, tx| {
-            let rx = read_line_from_path::<24>(rx).await;
-            let tx = read_line_from_path::<24>(tx).await;
-            let rx = rx.map(|rx| rx.parse::<u64>());
-            let tx = tx.map(|tx| tx.parse::<u64>());
-            match (rx, tx) {
-                (Ok(Ok(rx)), Ok(Ok(tx))) => {
-                    rx_bytes += rx;
-                    tx_bytes += tx;
-                }
-                (Ok(Err(err)), _) | (_, Ok(Err(err))) => {
-                    log::warn!("Error parsing rx/tx bytes: {err}");
-                }
-                (Err(err), _) | (_, Err(err)) => {
-                    log::warn!("Error reading rx/tx bytes: {err}");
+        let interfaces = interfaces(|name| self.filter.allows_name(name)).await?;
+        let mut inner = self.inner.get();
+        let previous = self.counters.take();
+        let mut counters = HashMap::with_capacity(previous.len());
+        let mut counted = Vec::with_capacity(previous.len());
+        let (mut rx_delta, mut tx_delta) = (0, 0);
+        let mut sampled = false;
+        let now = Instant::now();
+        let elapsed = inner.previous_update.map(|previous| now - previous);
+        let elapsed = elapsed.filter(|elapsed| !elapsed.is_zero());
+
+        for interface in interfaces {
+            if !interface.is_up() || !self.filter.allows(&interface) {
+                continue;
+            }
+            match interface.counters().await {
+                Ok((rx, tx)) => {
+                    let mut current = Counters { rx, tx, rates: None };
+                    // Interfaces that just came up count from the next update.
+                    if let Some(before) = previous.get(&interface.name) {
+                        let delta = (since(before.rx, rx), since(before.tx, tx));
+                        rx_delta += delta.0;
+                        tx_delta += delta.1;
+                        sampled = true;
+                        current.rates =
+                            elapsed.map(|elapsed| self.smooth(before.rates, delta, elapsed));
+                    }
+                    counters.insert(interface.name.clone(), current);
+                    counted.push(interface);
                 }
+                Err(err) => log::warn!("Error reading the counters of {}: {err}", interface.name),
             }
-        })
-        .await?;
-
-        let now = Instant::now();
-        let delta = delta.as_secs();
-
-        if delta > 0
-            && rx_bytes > inner.rx_bytes
-            && tx_bytes > inner.tx_bytes
-            && inner.tx_bytes != 0
-            && inner.rx_bytes != 0
-        {
-            inner.upload = (tx_bytes - inner.tx_bytes).checked_div(delta).unwrap_or(0);
-            inner.download = (rx_bytes - inner.rx_bytes).checked_div(delta).unwrap_or(0);
         }
 
-        inner.rx_bytes = rx_bytes;
-        inner.tx_bytes = tx_bytes;
+        // Unknown until an interface was counted at two updates in a row.
+        inner.rates = elapsed.filter(|_| sampled).map(|elapsed| {
+            let rates = self.smooth(inner.rates, (rx_delta, tx_delta), elapsed);
+            self.download_history.push(rates.0);
+            self.upload_history.push(rates.1);
+            rates
+        });
+
+        inner.rx_bytes = counters.values().map(|counters| counters.rx).sum();
+        inner.tx_bytes = counters.values().map(|counters| counters.tx).sum();
         inner.previous_update = Some(now);
+        self.inner.set(inner);
+        *self.counters.borrow_mut() = counters;
+        *self.active.borrow_mut() = Active::find(&counted, &self.failing).await;
+
+        Ok(())
+    }
+
+    fn tooltip(&self) -> impl Display {
+        NetTooltip(self)
+    }
+
+    fn value(&self) -> Option<Value> {
+        self.inner.get().rates?;
+        let (rx, tx) = self.rates();
+        Some(Value::Rate { rx, tx })
+    }
+
+    fn fields(&self) -> &'static [&'static str] {
+        &[
+            "download",
+            "upload",
+            "download_graph",
+            "upload_graph",
+            "icon",
+            "connection",
+            "interface",
+            "interface_download",
+            "interface_upload",
+            "ssid",
+            "signal",
+        ]
+    }
 
-        self.0.set(inner);
+    fn write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        let (download, upload) = self.rates();
+        let active = self.active.borrow();
+        let history = match name {
+            "download" => return Some(write!(out, "{}", NumFmt(download))),
+            "upload" => return Some(write!(out, "{}", NumFmt(upload))),
+            "icon" => return Some(out.write_str(self.connection().icon(&self.icons))),
+            "connection" => return Some(out.write_str(self.connection().as_str())),
+            "interface" => return Some(out.write_str(&active.as_ref()?.name)),
+            "interface_download" | "interface_upload" => {
+                let (download, upload) = self.interface_rates(&active.as_ref()?.name)?;
+                let rate = if name == "interface_download" { download } else { upload };
+                return Some(write!(out, "{}", NumFmt(rate)));
+            }
+            "ssid" => return Some(out.write_str(active.as_ref()?.ssid.as_ref()?)),
+            "signal" => return Some(write!(out, "{}", active.as_ref()?.signal?)),
+            "download_graph" => &self.download_history,
+            "upload_graph" => &self.upload_history,
+            _ => return None,
+        };
+        Some(match &self.sparkline {
+            Some(sparkline) => write!(out, "{}", sparkline.render(history)),
+            None => Ok(()),
+        })
+    }
+}
+
+struct NetTooltip<'a>(&'a NetMetric);
 
+impl Display for NetTooltip<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let inner = self.0.inner.get();
+        write!(f, "Received {}, sent {}", NumFmt(inner.rx_bytes), NumFmt(inner.tx_bytes))?;
+        match &*self.0.active.borrow() {
+            Some(Active { name, connection, ssid, signal }) => {
+                write!(f, "; {} on {name}", connection.as_str())?;
+                if let Some(ssid) = ssid {
+                    write!(f, " to {ssid}")?;
+                }
+                if let Some(signal) = signal {
+                    write!(f, ", {signal}% signal")?;
+                }
+            }
+            None => write!(f, "; offline")?,
+        }
+        let counters = self.0.counters.borrow();
+        let mut names: Vec<_> = counters.keys().collect();
+        names.sort();
+        for name in names {
+            if let Some((download, upload)) = self.0.interface_rates(name) {
+                write!(f, "; {name}: {}/s down, {}/s up", NumFmt(download), NumFmt(upload))?;
+            }
+        }
         Ok(())
     }
 }
 
-impl Display for NetMetricInner {
+impl Display for NetMetric {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "🔽{download}/s 🔼{upload}/s",
-            download = NumFmt(self.download),
-            upload = NumFmt(self.upload),
-        )
+        let (download, upload) = self.rates();
+        let (download, upload) = (NumFmt(download), NumFmt(upload));
+        let Icons { download: download_icon, upload: upload_icon, .. } = &self.icons;
+
+        match &self.sparkline {
+            Some(sparkline) => write!(
+                f,
+                "{download_icon}{} {download}/s {upload_icon}{} {upload}/s",
+                sparkline.render(&self.download_history),
+                sparkline.render(&self.upload_history),
+            ),
+            None => write!(f, "{download_icon}{download}/s {upload_icon}{upload}/s"),
+        }
     }
 }
@@ -0,0 +1,232 @@
+use std::{
+    cell::{Cell, OnceCell},
+    fmt::{self, Display, Formatter},
+    fs::File,
+    future::Future,
+    task::Poll,
+    time::Duration,
+};
+
+use tokio::io::{unix::AsyncFd, Interest};
+
+use crate::{
+    history::History,
+    icons::Icons,
+    procfs::pressure::{self, Pressure, PressureError, Resource},
+    Level, Metric, Value,
+};
+
+/// Stalled share of the last 10 seconds, in percent, the level turns warning
+/// at by default.
+pub const WARNING_PRESSURE: f32 = 10.;
+
+/// Window of the triggers. Shorter ones need `CAP_SYS_RESOURCE`.
+const TRIGGER_WINDOW: Duration = Duration::from_secs(2);
+
+type Sample = [Pressure; 3];
+
+/// The worst Pressure Stall Information of the CPU, memory and IO: how long
+/// tasks waited for them rather than how busy they are.
+///
+/// Besides its interval, the metric updates as soon as a resource crosses the
+/// threshold, through PSI triggers where the kernel allows them.
+#[derive(Debug)]
+pub struct PressureMetric {
+    sample: Cell<Option<Sample>>,
+    threshold: f32,
+    triggers: OnceCell<Vec<AsyncFd<File>>>,
+    icons: Icons,
+    history: History,
+}
+
+impl Default for PressureMetric {
+    fn default() -> Self {
+        Self {
+            sample: Cell::default(),
+            threshold: WARNING_PRESSURE,
+            triggers: OnceCell::new(),
+            icons: Icons::default(),
+            history: History::default(),
+        }
+    }
+}
+
+impl PressureMetric {
+    /// Raises the level to warning once some tasks stalled on a resource for
+    /// `percent` of the last 10 seconds, and to critical once all did.
+    #[must_use]
+    pub fn with_threshold(self, percent: f32) -> Self {
+        Self { threshold: percent, ..self }
+    }
+
+    /// Shows the pressure icon of `icons`.
+    #[must_use]
+    pub fn with_icons(self, icons: &Icons) -> Self {
+        Self { icons: icons.clone(), ..self }
+    }
+
+    /// The resource with the most stalls over the last 10 seconds.
+    fn worst(&self) -> Option<(Resource, Pressure)> {
+        let sample = self.sample.get()?;
+        Resource::ALL
+            .into_iter()
+            .zip(sample)
+            .max_by(|(_, a), (_, b)| a.some.avg10.total_cmp(&b.some.avg10))
+    }
+
+    /// A trigger per resource for [`Metric::changed`], none if the kernel
+    /// refuses them.
+    fn arm(&self) -> Vec<AsyncFd<File>> {
+        let stall = TRIGGER_WINDOW.mul_f32(self.threshold.clamp(0., 100.) / 100.);
+        let triggers: Result<Vec<_>, std::io::Error> = Resource::ALL
+            .into_iter()
+            .map(|resource| {
+                let file = pressure::trigger(resource, stall, TRIGGER_WINDOW)?;
+                // Deprecated by newer tokio for types that do not own their
+                // descriptor; `File` does, and the replacement is unsafe.
+                #[allow(deprecated)]
+                AsyncFd::with_interest(file, Interest::PRIORITY)
+            })
+            .collect();
+        triggers.unwrap_or_else(|err| {
+            log::info!("No PSI triggers, only polling the pressure: {err}");
+            Vec::new()
+        })
+    }
+}
+
+impl Metric for PressureMetric {
+    fn name(&self) -> &'static str {
+        "Pressure"
+    }
+
+    fn display(&self) -> impl Display {
+        self
+    }
+
+    async fn update(&self) -> Result<(), PressureError> {
+        let sample = async {
+            Ok::<_, PressureError>([
+                Pressure::read(Resource::Cpu).await?,
+                Pressure::read(Resource::Memory).await?,
+                Pressure::read(Resource::Io).await?,
+            ])
+        };
+        match sample.await {
+            Ok(sample) => {
+                self.sample.set(Some(sample));
+                if let Some(percentage) = self.percentage() {
+                    self.history.push(f64::from(percentage));
+                }
+                Ok(())
+            }
+            Err(err) => {
+                self.sample.set(None);
+                Err(err)
+            }
+        }
+    }
+
+    fn tooltip(&self) -> impl Display {
+        PressureTooltip(self.sample.get())
+    }
+
+    fn percentage(&self) -> Option<u8> {
+        self.worst().map(|(_, pressure)| pressure.some.avg10.round() as u8)
+    }
+
+    fn level(&self) -> Level {
+        let Some(sample) = self.sample.get() else {
+            return Level::Normal;
+        };
+        let above =
+            |stall: Option<pressure::Stall>| stall.is_some_and(|s| s.avg10 >= self.threshold);
+        if sample.iter().any(|pressure| above(pressure.full)) {
+            Level::Critical
+        } else if sample.iter().any(|pressure| above(Some(pressure.some))) {
+            Level::Warning
+        } else {
+            Level::Normal
+        }
+    }
+
+    fn history(&self) -> Option<&History> {
+        Some(&self.history)
+    }
+
+    fn value(&self) -> Option<Value> {
+        self.percentage().map(Value::Percent)
+    }
+
+    fn fields(&self) -> &'static [&'static str] {
+        &["icon", "percent", "avg60", "resource", "cpu", "memory", "io"]
+    }
+
+    fn write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        let sample = self.sample.get()?;
+        let (resource, worst) = self.worst()?;
+        Some(match name {
+            "icon" => out.write_str(&self.icons.pressure),
+            "percent" => write!(out, "{:.0}", worst.some.avg10),
+            "avg60" => write!(out, "{:.0}", worst.some.avg60),
+            "resource" => out.write_str(resource.as_str()),
+            "cpu" => write!(out, "{:.0}", sample[0].some.avg10),
+            "memory" => write!(out, "{:.0}", sample[1].some.avg10),
+            "io" => write!(out, "{:.0}", sample[2].some.avg10),
+            _ => return None,
+        })
+    }
+
+    /// Resolves when a trigger fires, never if there are none.
+    async fn changed(&self) {
+        let triggers = self.triggers.get_or_init(|| self.arm());
+        let mut fired: Vec<_> = triggers
+            .iter()
+            .map(|trigger| Box::pin(trigger.ready(Interest::PRIORITY)))
+            .collect();
+        let ready = std::future::poll_fn(|cx| {
+            fired
+                .iter_mut()
+                .find_map(|ready| match ready.as_mut().poll(cx) {
+                    Poll::Ready(ready) => Some(ready),
+                    Poll::Pending => None,
+                })
+                .map_or(Poll::Pending, Poll::Ready)
+        });
+        match ready.await {
+            Ok(mut guard) => guard.clear_ready(),
+            Err(err) => {
+                log::error!("Error waiting for a PSI trigger: {err}");
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+}
+
+struct PressureTooltip(Option<Sample>);
+
+impl Display for PressureTooltip {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Some(sample) = self.0 else {
+            return write!(f, "Pressure: unknown");
+        };
+        write!(f, "Pressure over 10s/60s:")?;
+        for (resource, pressure) in Resource::ALL.into_iter().zip(sample) {
+            let Pressure { some, full } = pressure;
+            write!(f, " {} {:.1}%/{:.1}%", resource.as_str(), some.avg10, some.avg60)?;
+            if let Some(full) = full.filter(|_| resource != Resource::Cpu) {
+                write!(f, " (all {:.1}%/{:.1}%)", full.avg10, full.avg60)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for PressureMetric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some((resource, worst)) = self.worst() {
+            write!(f, "{} {:.0}% {}", self.icons.pressure, worst.some.avg10, resource.as_str())?;
+        }
+        Ok(())
+    }
+}
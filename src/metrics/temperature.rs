@@ -0,0 +1,310 @@
+use std::{
+    cell::RefCell,
+    fmt::{self, Display, Formatter},
+    io::ErrorKind,
+    path::Path,
+};
+
+use crate::{
+    history::History,
+    icons::Icons,
+    read_line::{read_line_from_path, ReadLineError},
+    Level, Metric, Value,
+};
+
+const HWMON_DIR: &str = "/sys/class/hwmon";
+const THERMAL_DIR: &str = "/sys/class/thermal";
+
+/// Degrees below the critical temperature the warning starts at, for sensors
+/// without a `temp*_max` or `hot` trip point.
+const WARNING_MARGIN: i64 = 15_000;
+
+type Name = heapless::String<32>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum TemperatureError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Error reading a line: {0}")]
+    ReadLine(#[from] ReadLineError),
+    #[error("Error parsing a number: {0}")]
+    ParseInt(#[from] std::num::ParseIntError),
+    #[error("No temperature sensor labelled {0:?}")]
+    NoSensor(String),
+}
+
+/// A sensor and its thresholds, in millidegrees Celsius.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Reading {
+    /// `temp*_label` of hwmon sensors, `type` of thermal zones.
+    label: Name,
+    /// `name` of the hwmon chip, e.g. `coretemp` or `nvme`.
+    chip: Name,
+    temperature: i64,
+    warning: Option<i64>,
+    critical: Option<i64>,
+}
+
+impl Reading {
+    fn matches(&self, query: &str) -> bool {
+        self.label == query || self.chip == query
+    }
+
+    fn warning(&self) -> Option<i64> {
+        self.warning.or(self.critical.map(|critical| critical - WARNING_MARGIN))
+    }
+}
+
+/// Whole degrees of a millidegree value.
+struct Celsius(i64);
+
+impl Display for Celsius {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", (self.0 + 500).div_euclid(1000))
+    }
+}
+
+/// Temperature of the hottest sensor, or of the one selected with
+/// [`TemperatureMetric::with_sensor`].
+#[derive(Debug, Default)]
+pub struct TemperatureMetric {
+    reading: RefCell<Option<Reading>>,
+    sensor: Option<String>,
+    icons: Icons,
+    /// Temperature, in °C.
+    history: History,
+}
+
+/// Reads a millidegree file, `None` if it does not exist.
+async fn read_millidegrees(path: impl AsRef<Path>) -> Result<Option<i64>, TemperatureError> {
+    match read_line_from_path::<24>(path).await {
+        Ok(value) => Ok(Some(value.trim().parse()?)),
+        Err(ReadLineError::Io(err)) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Reads a threshold file, `None` if it does not exist or cannot be read.
+async fn read_threshold(path: impl AsRef<Path>) -> Option<i64> {
+    read_millidegrees(path).await.ok().flatten()
+}
+
+/// Reads a name file, empty if it does not exist or is too long.
+async fn read_name(path: impl AsRef<Path>) -> Name {
+    let name = read_line_from_path::<32>(path).await;
+    name.ok().and_then(|name| Name::try_from(name.trim()).ok()).unwrap_or_default()
+}
+
+impl TemperatureMetric {
+    /// Shows the sensor whose label or chip name is `sensor`, e.g.
+    /// `Package id 0`, `Composite` or `acpitz`.
+    #[must_use]
+    pub fn with_sensor(self, sensor: impl Into<String>) -> Self {
+        Self { sensor: Some(sensor.into()), ..self }
+    }
+
+    /// Shows the temperature icon of `icons`.
+    #[must_use]
+    pub fn with_icons(self, icons: &Icons) -> Self {
+        Self { icons: icons.clone(), ..self }
+    }
+
+    /// Keeps `reading` in `best` if it is the selected sensor, or hotter than
+    /// `best` if none is selected.
+    fn offer(&self, best: &mut Option<Reading>, reading: Reading) {
+        let better = match (&self.sensor, &*best) {
+            (Some(sensor), None) => reading.matches(sensor),
+            (Some(_), Some(_)) => false,
+            (None, None) => true,
+            (None, Some(best)) => reading.temperature > best.temperature,
+        };
+        if better {
+            *best = Some(reading);
+        }
+    }
+
+    /// `temp*_input` of every hwmon chip.
+    async fn hwmon(&self, best: &mut Option<Reading>) -> Result<(), TemperatureError> {
+        let mut chips = match tokio::fs::read_dir(HWMON_DIR).await {
+            Ok(chips) => chips,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        while let Some(chip) = chips.next_entry().await? {
+            let dir = chip.path();
+            let name = read_name(dir.join("name")).await;
+            let mut files = tokio::fs::read_dir(&dir).await?;
+            while let Some(file) = files.next_entry().await? {
+                let file_name = file.file_name();
+                let Some(sensor) = file_name.to_str().and_then(|f| f.strip_suffix("_input")) else {
+                    continue;
+                };
+                if !sensor.starts_with("temp") {
+                    continue;
+                }
+                // Sensors of suspended devices, e.g. GPUs and NVMe drives, fail
+                // with EIO, ENODATA or EAGAIN.
+                let Ok(Some(temperature)) = read_millidegrees(file.path()).await else {
+                    continue;
+                };
+                let label = match read_name(dir.join(format!("{sensor}_label"))).await {
+                    label if label.is_empty() => name.clone(),
+                    label => label,
+                };
+                let reading = Reading {
+                    label,
+                    chip: name.clone(),
+                    temperature,
+                    warning: read_threshold(dir.join(format!("{sensor}_max"))).await,
+                    critical: read_threshold(dir.join(format!("{sensor}_crit"))).await,
+                };
+                self.offer(best, reading);
+            }
+        }
+        Ok(())
+    }
+
+    /// `temp` of every thermal zone, with its `hot` and `critical` trip points.
+    async fn thermal(&self, best: &mut Option<Reading>) -> Result<(), TemperatureError> {
+        let mut zones = match tokio::fs::read_dir(THERMAL_DIR).await {
+            Ok(zones) => zones,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        while let Some(zone) = zones.next_entry().await? {
+            if !zone.file_name().to_str().is_some_and(|name| name.starts_with("thermal_zone")) {
+                continue;
+            }
+            let dir = zone.path();
+            // Disabled zones fail to read.
+            let Ok(Some(temperature)) = read_millidegrees(dir.join("temp")).await else {
+                continue;
+            };
+            let mut reading = Reading {
+                label: read_name(dir.join("type")).await,
+                temperature,
+                ..Reading::default()
+            };
+            for trip in 0.. {
+                let kind = dir.join(format!("trip_point_{trip}_type"));
+                let Ok(kind) = read_line_from_path::<24>(kind).await else {
+                    break;
+                };
+                let threshold = read_threshold(dir.join(format!("trip_point_{trip}_temp")));
+                match kind.trim() {
+                    "hot" => reading.warning = threshold.await,
+                    "critical" => reading.critical = threshold.await,
+                    _ => {}
+                }
+            }
+            self.offer(best, reading);
+        }
+        Ok(())
+    }
+
+    async fn read(&self) -> Result<Option<Reading>, TemperatureError> {
+        let mut best = None;
+        self.hwmon(&mut best).await?;
+        self.thermal(&mut best).await?;
+        match (&self.sensor, &best) {
+            (Some(sensor), None) => Err(TemperatureError::NoSensor(sensor.clone())),
+            _ => Ok(best),
+        }
+    }
+}
+
+impl Metric for TemperatureMetric {
+    fn name(&self) -> &'static str {
+        "Temperature"
+    }
+
+    fn display(&self) -> impl Display {
+        self
+    }
+
+    async fn update(&self) -> Result<(), TemperatureError> {
+        match self.read().await {
+            Ok(reading) => {
+                if let Some(reading) = &reading {
+                    self.history.push(reading.temperature as f64 / 1000.);
+                }
+                *self.reading.borrow_mut() = reading;
+                Ok(())
+            }
+            Err(err) => {
+                *self.reading.borrow_mut() = None;
+                Err(err)
+            }
+        }
+    }
+
+    fn tooltip(&self) -> impl Display {
+        TemperatureTooltip(self)
+    }
+
+    fn level(&self) -> Level {
+        let reading = self.reading.borrow();
+        let Some(reading) = reading.as_ref() else {
+            return Level::Normal;
+        };
+        if reading.critical.is_some_and(|critical| reading.temperature >= critical) {
+            Level::Critical
+        } else if reading.warning().is_some_and(|warning| reading.temperature >= warning) {
+            Level::Warning
+        } else {
+            Level::Normal
+        }
+    }
+
+    fn history(&self) -> Option<&History> {
+        Some(&self.history)
+    }
+
+    fn value(&self) -> Option<Value> {
+        Some(Value::Temperature(self.reading.borrow().as_ref()?.temperature))
+    }
+
+    fn fields(&self) -> &'static [&'static str] {
+        &["icon", "celsius", "label"]
+    }
+
+    fn write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        let reading = self.reading.borrow();
+        let reading = reading.as_ref()?;
+        Some(match name {
+            "icon" => out.write_str(&self.icons.temperature),
+            "celsius" => write!(out, "{}", Celsius(reading.temperature)),
+            "label" => out.write_str(&reading.label),
+            _ => return None,
+        })
+    }
+}
+
+struct TemperatureTooltip<'a>(&'a TemperatureMetric);
+
+impl Display for TemperatureTooltip<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let reading = self.0.reading.borrow();
+        let Some(reading) = reading.as_ref() else {
+            return write!(f, "Temperature: unknown");
+        };
+        write!(f, "{}", reading.label)?;
+        if !reading.chip.is_empty() && reading.chip != reading.label {
+            write!(f, " ({})", reading.chip)?;
+        }
+        write!(f, ": {}°C", Celsius(reading.temperature))?;
+        if let Some(critical) = reading.critical {
+            write!(f, ", critical at {}°C", Celsius(critical))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TemperatureMetric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(reading) = self.reading.borrow().as_ref() {
+            write!(f, "{} {}°C", self.icons.temperature, Celsius(reading.temperature))?;
+        }
+        Ok(())
+    }
+}
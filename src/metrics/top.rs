@@ -0,0 +1,230 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use crate::{
+    metrics::net::NumFmt,
+    procfs::{
+        process::{self, Comm, ProcessError, ProcessStat, ProcessStatus},
+        stat::{self, StatError},
+        Stat,
+    },
+    Metric, Value,
+};
+
+/// What makes a process the top one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Sort {
+    #[default]
+    Cpu,
+    /// Resident memory.
+    Memory,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Expected `cpu` or `memory`")]
+pub struct ParseSortError;
+
+impl FromStr for Sort {
+    type Err = ParseSortError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cpu" => Ok(Sort::Cpu),
+            "memory" => Ok(Sort::Memory),
+            _ => Err(ParseSortError),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum TopError {
+    #[error("Error reading /proc/stat: {0}")]
+    Stat(#[from] StatError),
+    #[error("Error reading a process: {0}")]
+    Process(#[from] ProcessError),
+    #[error("The scan panicked or was cancelled: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+/// Every process at one point in time.
+#[derive(Debug, Default)]
+struct Scan {
+    /// Ticks of all CPUs since boot.
+    total: u64,
+    cores: usize,
+    processes: Vec<(ProcessStat, Option<u64>)>,
+}
+
+impl Scan {
+    /// Reads [`stat::PATH`] and the stat file of every process, and the status
+    /// file too if `rss` is set. Processes that exit meanwhile are skipped.
+    fn read(rss: bool) -> Result<Self, TopError> {
+        let stat: Stat = std::fs::read_to_string(stat::PATH).map_err(StatError::from)?.parse()?;
+        let mut scan =
+            Scan { total: stat.cpu.total(), cores: stat.cores.len().max(1), processes: Vec::new() };
+        for pid in process::pids().map_err(ProcessError::from)? {
+            let Ok(process) = ProcessStat::read(pid) else {
+                continue;
+            };
+            let rss = if rss { ProcessStatus::read(pid).ok().and_then(|s| s.rss) } else { None };
+            scan.processes.push((process, rss));
+        }
+        Ok(scan)
+    }
+}
+
+/// Ticks of every process of the previous scan, by pid.
+#[derive(Debug, Default)]
+struct Previous {
+    total: u64,
+    /// Start time and ticks.
+    ticks: HashMap<u32, (u64, u64)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Top {
+    pid: u32,
+    comm: Comm,
+    /// Percentage of one core since the previous update, like `top`.
+    cpu: u32,
+    /// Resident memory in KiB.
+    rss: Option<u64>,
+}
+
+/// The process using the most CPU or memory, to see at a glance what keeps the
+/// cat of [`CpuMetric`](crate::metrics::CpuMetric) running.
+#[derive(Debug, Default)]
+pub struct TopMetric {
+    previous: RefCell<Option<Previous>>,
+    top: RefCell<Option<Top>>,
+    sort: Sort,
+}
+
+impl TopMetric {
+    #[must_use]
+    pub fn with_sort(self, sort: Sort) -> Self {
+        Self { sort, ..self }
+    }
+
+    /// Diffs `scan` with the previous one and picks the top process. `None`
+    /// on the first scan when sorting by CPU.
+    fn pick(&self, scan: Scan) -> Option<Top> {
+        let previous = self.previous.borrow_mut().replace(Previous {
+            total: scan.total,
+            ticks: scan
+                .processes
+                .iter()
+                .map(|(process, _)| (process.pid, (process.starttime, process.ticks())))
+                .collect(),
+        });
+        let elapsed = previous.as_ref().map(|previous| scan.total.saturating_sub(previous.total));
+        // A process missing from the previous scan started after it.
+        let cpu = |process: &ProcessStat| -> Option<u32> {
+            let elapsed = elapsed.filter(|&elapsed| elapsed > 0)?;
+            let before = match previous.as_ref()?.ticks.get(&process.pid) {
+                Some(&(start, ticks)) if start == process.starttime => ticks,
+                _ => 0,
+            };
+            let ticks = process.ticks().saturating_sub(before);
+            Some((ticks * 100 * scan.cores as u64 / elapsed) as u32)
+        };
+
+        let (process, rss) = match self.sort {
+            Sort::Cpu if elapsed.is_none() => return None,
+            Sort::Cpu => scan.processes.into_iter().max_by_key(|(process, _)| cpu(process))?,
+            Sort::Memory => scan.processes.into_iter().max_by_key(|&(_, rss)| rss)?,
+        };
+        Some(Top { pid: process.pid, cpu: cpu(&process).unwrap_or(0), comm: process.comm, rss })
+    }
+}
+
+impl Metric for TopMetric {
+    fn name(&self) -> &'static str {
+        "Top"
+    }
+
+    fn display(&self) -> impl Display {
+        self
+    }
+
+    async fn update(&self) -> Result<(), TopError> {
+        let rss = self.sort == Sort::Memory;
+        let scan = tokio::task::spawn_blocking(move || Scan::read(rss)).await?;
+        match scan {
+            Ok(scan) => {
+                let mut top = self.pick(scan);
+                // Sorting by CPU only reads the status file of the winner.
+                if let Some(top) = top.as_mut().filter(|top| top.rss.is_none()) {
+                    let status = tokio::task::spawn_blocking({
+                        let pid = top.pid;
+                        move || ProcessStatus::read(pid)
+                    });
+                    top.rss = status.await?.ok().and_then(|status| status.rss);
+                }
+                *self.top.borrow_mut() = top;
+                Ok(())
+            }
+            Err(err) => {
+                *self.top.borrow_mut() = None;
+                Err(err)
+            }
+        }
+    }
+
+    fn tooltip(&self) -> impl Display {
+        TopTooltip(self)
+    }
+
+    fn value(&self) -> Option<Value> {
+        Value::text(&self.top.borrow().as_ref()?.comm)
+    }
+
+    fn fields(&self) -> &'static [&'static str] {
+        &["name", "pid", "cpu", "rss"]
+    }
+
+    fn write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        let top = self.top.borrow();
+        let top = top.as_ref()?;
+        Some(match name {
+            "name" => out.write_str(&top.comm),
+            "pid" => write!(out, "{}", top.pid),
+            "cpu" => write!(out, "{}", top.cpu),
+            "rss" => write!(out, "{}", NumFmt(top.rss? * 1024)),
+            _ => return None,
+        })
+    }
+}
+
+struct TopTooltip<'a>(&'a TopMetric);
+
+impl Display for TopTooltip<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let top = self.0.top.borrow();
+        let Some(top) = top.as_ref() else {
+            return write!(f, "Top process: unknown");
+        };
+        write!(f, "Top process: {} (pid {}), {}% CPU", top.comm, top.pid, top.cpu)?;
+        if let Some(rss) = top.rss {
+            write!(f, ", {} resident", NumFmt(rss * 1024))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TopMetric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let top = self.top.borrow();
+        let Some(top) = top.as_ref() else {
+            return Ok(());
+        };
+        match (self.sort, top.rss) {
+            (Sort::Memory, Some(rss)) => write!(f, "{} {}", top.comm, NumFmt(rss * 1024)),
+            _ => write!(f, "{} {}%", top.comm, top.cpu),
+        }
+    }
+}
@@ -1,59 +1,97 @@
-use crate::{CommonError, Metric};
-use std::{cell::Cell, fmt::Display};
+use crate::{history::History, icons::Icons, CommonError, Level, Metric, Value};
+use std::{
+    cell::Cell,
+    fmt::{self, Display},
+};
 use tokio::process::Command;
 
-#[derive(Debug, Default)]
+/// Updates listed by `checkupdates`, at least one.
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    count: usize,
+    /// Whether a new kernel is among them.
+    kernel: bool,
+}
+
+impl Display for Pending {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.count)
+    }
+}
+
+/// Pending updates, hidden while there are none. Kernel updates raise the
+/// level to warning.
+#[derive(Debug, Default, Metric)]
+#[metric(
+    name = "Updates",
+    sample = sample,
+    optional,
+    reset_on_error,
+    icon = updates,
+    tooltip = pending_tooltip,
+    level = pending_level,
+    value = pending_value
+)]
 pub struct UpdatesMetric {
-    system_update: Cell<bool>,
-    updates_count: Cell<usize>,
+    #[metric(state)]
+    count: Cell<Option<Pending>>,
+    #[metric(history)]
+    history: History,
+    #[metric(icons)]
+    icons: Icons,
 }
 
-impl Metric for UpdatesMetric {
-    fn name(&self) -> &'static str {
-        "Updates"
+impl UpdatesMetric {
+    /// Shows the updates icon of `icons`.
+    #[must_use]
+    pub fn with_icons(self, icons: &Icons) -> Self {
+        Self { icons: icons.clone(), ..self }
     }
 
-    fn display(&self) -> impl Display {
-        self
+    /// Number of pending updates.
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.count.get().map_or(0, |pending| pending.count)
     }
 
-    #[allow(clippy::unnecessary_map_or)]
-    async fn update(&self) -> Result<(), CommonError> {
-        match try {
-            let result = Command::new("sh").arg("-c").arg("checkupdates").output().await?;
+    async fn sample(&self) -> Result<Option<Pending>, CommonError> {
+        let result = Command::new("sh").arg("-c").arg("checkupdates").output().await?;
 
-            if !result.status.success() {
-                return Err(CommonError::UnsuccessfullShell(result.status));
-            }
+        if !result.status.success() {
+            return Err(CommonError::UnsuccessfullShell(result.status));
+        }
 
-            let updates = std::str::from_utf8(&result.stdout)?;
+        let updates = std::str::from_utf8(&result.stdout)?;
+        let pending = Pending { count: updates.lines().count(), kernel: updates.contains("linux") };
+        Ok((pending.count > 0).then_some(pending))
+    }
 
-            self.system_update.set(updates.contains("linux"));
-            self.updates_count.set(updates.lines().count());
-        } {
-            Ok(()) => Ok(()),
-            Err(err) => {
-                self.system_update.set(false);
-                self.updates_count.set(0);
-                Err(err)
-            }
+    fn pending_tooltip(&self) -> impl Display {
+        UpdatesTooltip(self.count.get())
+    }
+
+    fn pending_level(&self) -> Level {
+        match self.count.get() {
+            Some(Pending { kernel: true, .. }) => Level::Warning,
+            _ => Level::Normal,
         }
     }
-}
 
-impl Display for UpdatesMetric {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let updates_count = self.updates_count.get();
-        let system_update = self.system_update.get();
+    /// Zero while there are none, or after a failed check.
+    fn pending_value(&self) -> Value {
+        Value::Count(self.count())
+    }
+}
 
-        if updates_count == 0 {
-            return Ok(());
-        }
+struct UpdatesTooltip(Option<Pending>);
 
-        if system_update {
-            write!(f, "🔁! {updates_count}")
-        } else {
-            write!(f, "🔁 {updates_count}")
+impl Display for UpdatesTooltip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Pending { count, kernel } = self.0.unwrap_or(Pending { count: 0, kernel: false });
+        write!(f, "{count} pending updates")?;
+        if kernel {
+            write!(f, ", including the kernel")?;
         }
+        Ok(())
     }
 }
@@ -1,54 +1,35 @@
-use crate::Metric;
+use crate::{icons::Icons, CommonError, Metric};
 
-use std::{cell::Cell, fmt::Display};
+use std::cell::Cell;
 use tokio::process::Command;
 
 type Locale = heapless::String<32>;
 
-#[derive(Default)]
+#[derive(Default, Metric)]
+#[metric(name = "xkblayout", sample = sample, icon = layout, reset_on_error)]
 pub struct XkbLayoutMetric {
-    locale: Cell<Option<Locale>>,
+    #[metric(state)]
+    layout: Cell<Option<Locale>>,
+    #[metric(icons)]
+    icons: Icons,
 }
 
-impl Metric for XkbLayoutMetric {
-    fn name(&self) -> &'static str {
-        "xkblayout"
+impl XkbLayoutMetric {
+    /// Shows the layout icon of `icons`.
+    #[must_use]
+    pub fn with_icons(self, icons: &Icons) -> Self {
+        Self { icons: icons.clone(), ..self }
     }
 
-    fn display(&self) -> impl Display {
-        self
-    }
-
-    async fn update(&self) -> Result<(), crate::CommonError> {
-        match try {
-            let out = Command::new("sh").arg("-c").arg("xkb-switch").output().await?;
-
-            if !out.status.success() {
-                return Err(crate::CommonError::UnsuccessfullShell(out.status));
-            }
-
-            let loc = std::str::from_utf8(&out.stdout)?;
+    async fn sample(&self) -> Result<Locale, CommonError> {
+        let out = Command::new("sh").arg("-c").arg("xkb-switch").output().await?;
 
-            let locale = Locale::try_from(loc.strip_suffix('\n').unwrap_or(loc));
-            self.locale.set(Some(locale.map_err(|()| crate::CommonError::Capacity)?));
-        } {
-            Ok(()) => Ok(()),
-            Err(err) => {
-                self.locale.set(None);
-                Err(err)
-            }
+        if !out.status.success() {
+            return Err(CommonError::UnsuccessfullShell(out.status));
         }
-    }
-}
 
-impl Display for XkbLayoutMetric {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let Some(locale) = self.locale.take() {
-            let res = write!(f, "🌍 {locale}");
-            self.locale.set(Some(locale));
-            res
-        } else {
-            Ok(())
-        }
+        let loc = std::str::from_utf8(&out.stdout)?;
+
+        Locale::try_from(loc.strip_suffix('\n').unwrap_or(loc)).map_err(|()| CommonError::Capacity)
     }
 }
@@ -0,0 +1,189 @@
+//! The SSID of a Wi-Fi interface, asked to the kernel over the nl80211 generic
+//! netlink family. See `linux/netlink.h`, `linux/genetlink.h` and
+//! `linux/nl80211.h` for the constants.
+
+use std::{
+    io::{self, ErrorKind},
+    os::fd::OwnedFd,
+    time::Duration,
+};
+
+use rustix::net::{
+    bind,
+    netlink::{self, SocketAddrNetlink},
+    recv, send, socket, sockopt, AddressFamily, RecvFlags, SendFlags, SocketType,
+};
+
+const NLMSG_ERROR: u16 = 2;
+const NLM_F_REQUEST: u16 = 1;
+/// Attribute types may carry the nested and byte order flags in the top bits.
+const NLA_TYPE_MASK: u16 = 0x3fff;
+
+const GENL_ID_CTRL: u16 = 0x10;
+const CTRL_CMD_GETFAMILY: u8 = 3;
+const CTRL_ATTR_FAMILY_ID: u16 = 1;
+const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+
+const NL80211_CMD_GET_INTERFACE: u8 = 5;
+const NL80211_ATTR_IFINDEX: u16 = 3;
+const NL80211_ATTR_SSID: u16 = 52;
+
+/// `nlmsghdr` followed by `genlmsghdr`.
+const HEADER_LEN: usize = 16 + 4;
+
+/// How long the kernel gets to answer.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The SSID of the network the interface with index `index` is connected to,
+/// `None` while disconnected. Blocks.
+///
+/// # Errors
+/// If nl80211 is unavailable, e.g. without any Wi-Fi driver, or if the
+/// interface is not a Wi-Fi one.
+pub fn ssid(index: u32) -> io::Result<Option<String>> {
+    let socket = Socket::open()?;
+    let family =
+        socket.request(GENL_ID_CTRL, CTRL_CMD_GETFAMILY, CTRL_ATTR_FAMILY_NAME, b"nl80211\0")?;
+    let family = attribute(&family, CTRL_ATTR_FAMILY_ID)
+        .and_then(|id| Some(u16::from_ne_bytes(id.get(..2)?.try_into().ok()?)))
+        .ok_or_else(malformed)?;
+    let interface = socket.request(
+        family,
+        NL80211_CMD_GET_INTERFACE,
+        NL80211_ATTR_IFINDEX,
+        &index.to_ne_bytes(),
+    )?;
+    // SSIDs are up to 32 arbitrary bytes, though mostly UTF-8.
+    Ok(attribute(&interface, NL80211_ATTR_SSID)
+        .map(|ssid| String::from_utf8_lossy(ssid).into_owned()))
+}
+
+struct Socket(OwnedFd);
+
+impl Socket {
+    fn open() -> io::Result<Self> {
+        let fd = socket(AddressFamily::NETLINK, SocketType::RAW, Some(netlink::GENERIC))?;
+        bind(&fd, &SocketAddrNetlink::new(0, 0))?;
+        sockopt::set_socket_timeout(&fd, sockopt::Timeout::Recv, Some(TIMEOUT))?;
+        Ok(Self(fd))
+    }
+
+    /// Sends `command` with a single attribute to `family`, and returns the
+    /// attributes of the reply.
+    fn request(&self, family: u16, command: u8, kind: u16, payload: &[u8]) -> io::Result<Vec<u8>> {
+        let attribute_len = 4 + payload.len();
+        let len = HEADER_LEN + align(attribute_len);
+        let mut message = Vec::with_capacity(len);
+        message.extend((len as u32).to_ne_bytes());
+        message.extend(family.to_ne_bytes());
+        message.extend(NLM_F_REQUEST.to_ne_bytes());
+        // Sequence number and port id, which the kernel fills in.
+        message.extend([0; 8]);
+        // Command, version and padding.
+        message.extend([command, 1, 0, 0]);
+        message.extend((attribute_len as u16).to_ne_bytes());
+        message.extend(kind.to_ne_bytes());
+        message.extend(payload);
+        message.resize(len, 0);
+        send(&self.0, &message, SendFlags::empty())?;
+
+        let mut reply = vec![0; 8192];
+        let (received, _) = recv(&self.0, &mut reply[..], RecvFlags::empty())?;
+        reply.truncate(received);
+        let u16_at = |at: usize| Some(u16::from_ne_bytes(reply.get(at..at + 2)?.try_into().ok()?));
+        if u16_at(4).ok_or_else(malformed)? == NLMSG_ERROR {
+            // `nlmsgerr` starts with a negative errno.
+            let errno = reply.get(16..20).ok_or_else(malformed)?;
+            let errno = i32::from_ne_bytes(errno.try_into().map_err(|_| malformed())?);
+            return Err(io::Error::from_raw_os_error(-errno));
+        }
+        let len = u32::from_ne_bytes(reply[..4].try_into().map_err(|_| malformed())?) as usize;
+        if len < HEADER_LEN || len > reply.len() {
+            return Err(malformed());
+        }
+        reply.truncate(len);
+        reply.drain(..HEADER_LEN);
+        Ok(reply)
+    }
+}
+
+/// The payload of the first attribute of type `kind` in `attributes`.
+fn attribute(mut attributes: &[u8], kind: u16) -> Option<&[u8]> {
+    while let [len_low, len_high, kind_low, kind_high, ..] = *attributes {
+        let len = u16::from_ne_bytes([len_low, len_high]) as usize;
+        let payload = attributes.get(4..len)?;
+        if u16::from_ne_bytes([kind_low, kind_high]) & NLA_TYPE_MASK == kind {
+            return Some(payload);
+        }
+        attributes = attributes.get(align(len)..).unwrap_or_default();
+    }
+    None
+}
+
+/// Netlink messages and attributes are padded to 4 bytes.
+fn align(len: usize) -> usize {
+    len.next_multiple_of(4)
+}
+
+fn malformed() -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, "Malformed netlink reply")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An attribute of type `kind` around `payload`, padded.
+    fn nla(kind: u16, payload: &[u8]) -> Vec<u8> {
+        let len = 4 + payload.len();
+        let mut attribute = Vec::with_capacity(align(len));
+        attribute.extend((len as u16).to_ne_bytes());
+        attribute.extend(kind.to_ne_bytes());
+        attribute.extend(payload);
+        attribute.resize(align(len), 0);
+        attribute
+    }
+
+    #[test]
+    fn padded() {
+        // The 5 bytes of the name take 8 with the padding.
+        let reply = [
+            nla(NL80211_ATTR_IFINDEX, &3_u32.to_ne_bytes()),
+            nla(1, b"wlan0"),
+            nla(NL80211_ATTR_SSID, b"cafe"),
+        ]
+        .concat();
+        assert_eq!(attribute(&reply, 1), Some(&b"wlan0"[..]));
+        assert_eq!(attribute(&reply, NL80211_ATTR_SSID), Some(&b"cafe"[..]));
+        assert_eq!(attribute(&reply, NL80211_ATTR_IFINDEX), Some(&3_u32.to_ne_bytes()[..]));
+        assert_eq!(attribute(&reply, 2), None);
+        assert_eq!(attribute(&[], 2), None);
+    }
+
+    #[test]
+    fn nested() {
+        const NLA_F_NESTED: u16 = 0x8000;
+        let inner = [nla(1, b"a"), nla(2, b"bc")].concat();
+        let reply = [nla(7, b"x"), nla(8 | NLA_F_NESTED, &inner)].concat();
+        let nested = attribute(&reply, 8);
+        assert_eq!(nested.map(<[u8]>::len), Some(inner.len()));
+        assert_eq!(nested.and_then(|nested| attribute(nested, 2)), Some(&b"bc"[..]));
+        // Nested attributes are not searched from the outside.
+        assert_eq!(attribute(&reply, 2), None);
+    }
+
+    #[test]
+    fn truncated() {
+        let reply = [nla(1, b"abcd"), nla(NL80211_ATTR_SSID, b"network")].concat();
+        // Cut in the middle of the payload or of the header.
+        assert_eq!(attribute(&reply[..12], NL80211_ATTR_SSID), None);
+        assert_eq!(attribute(&reply[..10], NL80211_ATTR_SSID), None);
+        // The first attribute is whole, the padding of the last one is not needed.
+        assert_eq!(attribute(&reply[..8], 1), Some(&b"abcd"[..]));
+        assert_eq!(attribute(&reply[..reply.len() - 1], NL80211_ATTR_SSID), Some(&b"network"[..]));
+        // A length shorter than the header itself.
+        assert_eq!(attribute(&[2, 0, 1, 0, 0, 0, 0, 0], 1), None);
+        let zero = [0_u16.to_ne_bytes(), 1_u16.to_ne_bytes()].concat();
+        assert_eq!(attribute(&zero, 1), None);
+    }
+}
@@ -0,0 +1,95 @@
+//! Desktop notifications through the `org.freedesktop.Notifications` D-Bus
+//! interface.
+//!
+//! The call is made with `gdbus`, which talks to the session bus named by
+//! `$DBUS_SESSION_BUS_ADDRESS`, so notifications can be sent to a stand-in
+//! daemon on a private bus started with `dbus-run-session`.
+
+use std::fmt::{self, Display, Formatter, Write as _};
+
+use tokio::process::Command;
+
+use crate::CommonError;
+
+const APP_NAME: &str = "barstatus";
+
+/// How long the notification server shows a notification, in milliseconds.
+/// `-1` lets the server decide.
+const EXPIRE_TIMEOUT: &str = "-1";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification<'a> {
+    pub summary: &'a str,
+    pub body: &'a str,
+    pub urgency: Urgency,
+    /// Id returned for an earlier notification that this one replaces, or 0.
+    pub replaces: u32,
+}
+
+/// Quotes `s` as a `GVariant` text format string, so `gdbus` never
+/// interprets it.
+struct Quoted<'a>(&'a str);
+
+impl Display for Quoted<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            if matches!(c, '"' | '\\') {
+                f.write_char('\\')?;
+            }
+            f.write_char(c)?;
+        }
+        f.write_char('"')
+    }
+}
+
+impl Notification<'_> {
+    /// Shows the notification and returns the id the server assigned to it.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `gdbus` cannot be run, if there is no notification server, or
+    /// if its reply cannot be parsed.
+    pub async fn send(&self) -> Result<u32, CommonError> {
+        let urgency = self.urgency as u8;
+        let out = Command::new("gdbus")
+            .args([
+                "call",
+                "--session",
+                "--dest=org.freedesktop.Notifications",
+                "--object-path=/org/freedesktop/Notifications",
+                "--method=org.freedesktop.Notifications.Notify",
+                // So that the `-1` timeout is not taken for an option.
+                "--",
+                APP_NAME,
+                &self.replaces.to_string(),
+                "\"\"",
+                &Quoted(self.summary).to_string(),
+                &Quoted(self.body).to_string(),
+                "@as []",
+                &format!("{{\"urgency\": <byte {urgency}>}}"),
+                EXPIRE_TIMEOUT,
+            ])
+            // A call cut short by a timeout should not linger.
+            .kill_on_drop(true)
+            .output()
+            .await?;
+
+        if !out.status.success() {
+            return Err(CommonError::UnsuccessfullShell(out.status));
+        }
+
+        // The reply looks like `(uint32 7,)`.
+        let reply = std::str::from_utf8(&out.stdout)?;
+        let id = reply.trim().trim_start_matches("(uint32 ").trim_end_matches(",)");
+        Ok(id.parse()?)
+    }
+}
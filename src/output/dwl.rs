@@ -0,0 +1,56 @@
+use std::{
+    fmt::{self, Write as _},
+    io::Write,
+};
+
+use super::{
+    line::{write_escaped, Line, LineError, LineWriter, Markup},
+    Frame, Sink, Styles,
+};
+
+/// Prints the bar without padding for compositors reading status text from a
+/// child's stdout, like dwl and somebar. With colours enabled it uses the
+/// `^fg(#rrggbb)` escapes of dwl's bar patch.
+#[derive(Debug)]
+pub struct DwlSink<W> {
+    lines: LineWriter<W>,
+    styles: Option<Styles>,
+}
+
+impl<W: Write> DwlSink<W> {
+    /// Colours are only written if `styles` is set.
+    pub fn new(writer: W, styles: Option<Styles>) -> Self {
+        Self { lines: LineWriter::new(writer), styles }
+    }
+}
+
+fn render(frame: &Frame, styles: Option<&Styles>, line: &mut Line) -> fmt::Result {
+    for (i, segment) in frame.segments().enumerate() {
+        if i != 0 {
+            line.write_str(frame.separator())?;
+        }
+        // Only the bar patch treats `^` as the start of an escape.
+        let Some(styles) = styles else {
+            write_escaped(line, segment.text, Markup::Plain)?;
+            continue;
+        };
+        match styles.color(&segment) {
+            Some(color) => {
+                write!(line, "^fg({color})")?;
+                write_escaped(line, segment.text, Markup::Double('^'))?;
+                line.write_str("^fg()")?;
+            }
+            None => write_escaped(line, segment.text, Markup::Double('^'))?,
+        }
+    }
+    Ok(())
+}
+
+impl<W: Write> Sink for DwlSink<W> {
+    type Error = LineError;
+
+    fn write_frame(&mut self, frame: &Frame) -> Result<(), LineError> {
+        let Self { lines, styles } = self;
+        lines.write(|line| render(frame, styles.as_ref(), line))
+    }
+}
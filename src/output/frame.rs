@@ -0,0 +1,211 @@
+use std::{
+    fmt::{self, Display, Formatter, Write},
+    time::Duration,
+};
+
+use crate::{Level, Metric};
+
+const CAPACITY: usize = 1024;
+/// Most metrics a frame can hold.
+pub const MAX_SEGMENTS: usize = 16;
+pub const DEFAULT_SEPARATOR: &str = " | ";
+
+/// One rendered metric, borrowed from a [`Frame`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment<'a> {
+    pub name: &'static str,
+    pub text: &'a str,
+    pub tooltip: &'a str,
+    pub percentage: Option<u8>,
+    pub level: Level,
+    pub animation: Option<(char, char)>,
+}
+
+/// Something that can put a rendered frame in front of the user.
+pub trait Sink {
+    type Error: std::error::Error;
+
+    /// # Errors
+    /// If the frame could not be shown. The frame loop stops on the first error.
+    fn write_frame(&mut self, frame: &Frame) -> Result<(), Self::Error>;
+}
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameError {
+    #[error("Frame buffer is full")]
+    Capacity,
+    #[error("Too many segments in a frame")]
+    TooManySegments,
+    #[error("Formatting error")]
+    Fmt,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    name: &'static str,
+    start: usize,
+    text_end: usize,
+    end: usize,
+    percentage: Option<u8>,
+    level: Level,
+    animation: Option<(char, char)>,
+}
+
+/// Fixed-size buffer the metrics of a single frame are rendered into, so the
+/// frame loop does not allocate.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    buf: [u8; CAPACITY],
+    len: usize,
+    spans: heapless::Vec<Span, MAX_SEGMENTS>,
+    period: Option<Duration>,
+    separator: &'static str,
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEPARATOR)
+    }
+}
+
+struct BufWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    overflow: bool,
+}
+
+impl Write for BufWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let Some(dst) = self.buf.get_mut(self.len..self.len + s.len()) else {
+            self.overflow = true;
+            return Err(fmt::Error);
+        };
+        dst.copy_from_slice(s.as_bytes());
+        self.len += s.len();
+        Ok(())
+    }
+}
+
+impl Frame {
+    /// Sinks put `separator` between the segments.
+    #[must_use]
+    pub fn new(separator: &'static str) -> Self {
+        Self { buf: [0; CAPACITY], len: 0, spans: heapless::Vec::new(), period: None, separator }
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.spans.clear();
+        self.period = None;
+    }
+
+    fn write(&mut self, value: impl Display) -> Result<usize, FrameError> {
+        let mut writer = BufWriter { buf: &mut self.buf, len: self.len, overflow: false };
+        match write!(writer, "{value}") {
+            Ok(()) => Ok(writer.len),
+            Err(_) if writer.overflow => Err(FrameError::Capacity),
+            Err(_) => Err(FrameError::Fmt),
+        }
+    }
+
+    /// Renders `metric` into the frame. Metrics that display nothing are
+    /// skipped. On error the frame is left as it was before the call.
+    ///
+    /// # Errors
+    /// If the buffer or the segment list is full, or the metric fails to format.
+    pub fn push(&mut self, metric: &impl Metric) -> Result<(), FrameError> {
+        if self.spans.is_full() {
+            return Err(FrameError::TooManySegments);
+        }
+        let start = self.len;
+        let text_end = self.write(metric.display())?;
+        if text_end == start {
+            return Ok(());
+        }
+        self.len = text_end;
+        let end = match self.write(metric.tooltip()) {
+            Ok(end) => end,
+            Err(err) => {
+                self.len = start;
+                return Err(err);
+            }
+        };
+        self.len = end;
+
+        let span = Span {
+            name: metric.name(),
+            start,
+            text_end,
+            end,
+            percentage: metric.percentage(),
+            level: metric.level(),
+            animation: metric.animation_frames(),
+        };
+        if self.spans.push(span).is_err() {
+            unreachable!("Checked for a free slot above");
+        }
+        if let Some(period) = metric.frame_period() {
+            self.period = Some(self.period.map_or(period, |p| p.min(period)));
+        }
+
+        Ok(())
+    }
+
+    fn str(&self, from: usize, to: usize) -> &str {
+        // Spans only ever cover whole `write_str` calls, so they are valid UTF-8.
+        std::str::from_utf8(&self.buf[from..to]).unwrap_or_default()
+    }
+
+    pub fn segments(&self) -> impl Iterator<Item = Segment<'_>> + Clone + '_ {
+        self.spans.iter().map(|span| Segment {
+            name: span.name,
+            text: self.str(span.start, span.text_end),
+            tooltip: self.str(span.text_end, span.end),
+            percentage: span.percentage,
+            level: span.level,
+            animation: span.animation,
+        })
+    }
+
+    #[must_use]
+    pub fn segment(&self, name: &str) -> Option<Segment<'_>> {
+        self.segments().find(|segment| segment.name == name)
+    }
+
+    /// How soon any of the segments wants to be redrawn, if at all.
+    #[must_use]
+    pub fn period(&self) -> Option<Duration> {
+        self.period
+    }
+
+    /// The highest [`Level`] among the segments.
+    #[must_use]
+    pub fn level(&self) -> Level {
+        self.spans.iter().map(|span| span.level).max().unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn separator(&self) -> &'static str {
+        self.separator
+    }
+
+    /// Texts of all segments joined with the separator.
+    #[must_use]
+    pub fn joined(&self) -> impl Display + '_ {
+        Joined(self)
+    }
+}
+
+struct Joined<'a>(&'a Frame);
+
+impl Display for Joined<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.0.segments().enumerate() {
+            if i != 0 {
+                f.write_str(self.0.separator)?;
+            }
+            f.write_str(segment.text)?;
+        }
+        Ok(())
+    }
+}
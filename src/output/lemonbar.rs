@@ -0,0 +1,66 @@
+use std::{
+    fmt::{self, Write as _},
+    io::Write,
+};
+
+use super::{
+    line::{write_escaped, Line, LineError, LineWriter, Markup},
+    Frame, Sink, Styles,
+};
+
+/// Prints one lemonbar formatted line per change, right aligned. Clicking a
+/// segment makes lemonbar print its action, so pipe lemonbar into `sh`.
+#[derive(Debug)]
+pub struct LemonbarSink<W> {
+    lines: LineWriter<W>,
+    styles: Styles,
+}
+
+impl<W: Write> LemonbarSink<W> {
+    pub fn new(writer: W, styles: Styles) -> Self {
+        Self { lines: LineWriter::new(writer), styles }
+    }
+}
+
+fn render(frame: &Frame, styles: &Styles, line: &mut Line) -> fmt::Result {
+    line.write_str("%{r}")?;
+    for (i, segment) in frame.segments().enumerate() {
+        if i != 0 {
+            line.write_str(frame.separator())?;
+        }
+        let action = styles.action(segment.name);
+        let color = styles.color(&segment);
+
+        if let Some(action) = action {
+            line.write_str("%{A:")?;
+            for c in action.chars() {
+                if c == ':' {
+                    line.write_char('\\')?;
+                }
+                line.write_char(c)?;
+            }
+            line.write_str(":}")?;
+        }
+        match color {
+            Some(color) => {
+                write!(line, "%{{F{color}}}")?;
+                write_escaped(line, segment.text, Markup::Double('%'))?;
+                line.write_str("%{F-}")?;
+            }
+            None => write_escaped(line, segment.text, Markup::Double('%'))?,
+        }
+        if action.is_some() {
+            line.write_str("%{A}")?;
+        }
+    }
+    Ok(())
+}
+
+impl<W: Write> Sink for LemonbarSink<W> {
+    type Error = LineError;
+
+    fn write_frame(&mut self, frame: &Frame) -> Result<(), LineError> {
+        let Self { lines, styles } = self;
+        lines.write(|line| render(frame, styles, line))
+    }
+}
@@ -0,0 +1,94 @@
+use std::{
+    fmt::{self, Display, Write as _},
+    io::Write,
+};
+
+pub(crate) type Line = heapless::String<4096>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum LineError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Capacity error")]
+    Capacity,
+}
+
+/// The markup of an output, which segment text must not be able to inject.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Markup {
+    /// No markup, e.g. dwl without the bar patch.
+    Plain,
+    /// Markup that starts with this character, and whose doubling is a literal
+    /// one: `%` for lemonbar, `#` for tmux and `^` for dwl's bar patch.
+    Double(char),
+    /// Pango markup, for Waybar: `&`, `<`, `>` and quotes become entities.
+    Pango,
+}
+
+struct Escaper<'a, W> {
+    out: &'a mut W,
+    markup: Markup,
+}
+
+impl<W: fmt::Write> fmt::Write for Escaper<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            match (self.markup, c) {
+                (Markup::Double(special), c) if c == special => {
+                    self.out.write_char(c)?;
+                    self.out.write_char(c)?;
+                }
+                (Markup::Pango, '&') => self.out.write_str("&amp;")?,
+                (Markup::Pango, '<') => self.out.write_str("&lt;")?,
+                (Markup::Pango, '>') => self.out.write_str("&gt;")?,
+                (Markup::Pango, '"') => self.out.write_str("&quot;")?,
+                (Markup::Pango, '\'') => self.out.write_str("&apos;")?,
+                (_, c) if c.is_control() => self.out.write_char(' ')?,
+                (_, c) => self.out.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes segment text, which may come from other users or from the network
+/// (process names, SSIDs), without letting it inject `markup`. Control
+/// characters, line breaks included, would end or garble the line and become
+/// spaces.
+pub(crate) fn write_escaped(
+    out: &mut impl fmt::Write,
+    text: impl Display,
+    markup: Markup,
+) -> fmt::Result {
+    write!(Escaper { out, markup }, "{text}")
+}
+
+/// Writes one line per frame, skipping frames that render the same as the
+/// previous one, for outputs that read status lines from a pipe.
+#[derive(Debug)]
+pub(crate) struct LineWriter<W> {
+    writer: W,
+    previous: Line,
+}
+
+impl<W: Write> LineWriter<W> {
+    pub(crate) fn new(writer: W) -> Self {
+        Self { writer, previous: Line::new() }
+    }
+
+    pub(crate) fn write(
+        &mut self,
+        render: impl FnOnce(&mut Line) -> fmt::Result,
+    ) -> Result<(), LineError> {
+        let mut line = Line::new();
+        render(&mut line).map_err(|_| LineError::Capacity)?;
+
+        if line != self.previous {
+            writeln!(self.writer, "{line}")?;
+            self.writer.flush()?;
+            self.previous = line;
+        }
+
+        Ok(())
+    }
+}
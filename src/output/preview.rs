@@ -0,0 +1,96 @@
+use std::{
+    fmt::{self, Write as _},
+    io::Write,
+};
+
+use super::{
+    line::{Line, LineError, LineWriter},
+    Frame, Sink, Styles,
+};
+use crate::stats::{Stats, UpdateStats};
+
+/// Longest error message shown in the debug pane, in characters.
+const ERROR_WIDTH: usize = 60;
+
+const HEADER: &str =
+    "\x1b[1mmetric              interval   last update   latency   frame   last error\x1b[0m\x1b[K\n";
+
+/// Draws the bar in a terminal with ANSI colours, and a debug pane with the
+/// update statistics of every metric underneath.
+#[derive(Debug)]
+pub struct PreviewSink<'a, W> {
+    lines: LineWriter<W>,
+    styles: Styles,
+    stats: &'a Stats,
+}
+
+impl<'a, W: Write> PreviewSink<'a, W> {
+    pub fn new(writer: W, styles: Styles, stats: &'a Stats) -> Self {
+        Self { lines: LineWriter::new(writer), styles, stats }
+    }
+}
+
+fn render_stats(frame: &Frame, stats: &UpdateStats, line: &mut Line) -> fmt::Result {
+    let UpdateStats { name, interval, last_update, latency, ref last_error } = *stats;
+    let animation = frame.segment(name).and_then(|segment| segment.animation);
+
+    write!(
+        line,
+        "{name:<18} {interval:>9.1?} {:>9.1?} ago {latency:>9.1?}",
+        last_update.elapsed()
+    )?;
+    match animation {
+        Some((current, next)) => write!(line, "   {current} → {next}")?,
+        None => line.write_str("   -    ")?,
+    }
+    if let Some(error) = last_error {
+        line.write_str("   ")?;
+        for c in error.chars().take(ERROR_WIDTH) {
+            line.write_char(c)?;
+        }
+    }
+    line.write_str("\x1b[K\n")
+}
+
+fn render(frame: &Frame, styles: &Styles, stats: &Stats, line: &mut Line) -> fmt::Result {
+    // Redraw in place instead of clearing the screen to avoid flicker.
+    line.write_str("\x1b[H")?;
+    for (i, segment) in frame.segments().enumerate() {
+        if i != 0 {
+            line.write_str(frame.separator())?;
+        }
+        match styles.color(&segment) {
+            Some(color) => {
+                let (r, g, b) = (color.r, color.g, color.b);
+                write!(line, "\x1b[38;2;{r};{g};{b}m{}\x1b[0m", segment.text)?;
+            }
+            None => line.write_str(segment.text)?,
+        }
+    }
+    line.write_str("\x1b[K\n\x1b[K\n")?;
+
+    line.write_str(HEADER)?;
+    let mut result = Ok(());
+    stats.for_each(|stats| {
+        if result.is_ok() {
+            result = render_stats(frame, stats, line);
+        }
+    });
+    result?;
+
+    match frame.period() {
+        Some(period) => write!(line, "\x1b[K\nnext frame in {period:.1?}\x1b[K")?,
+        None => line.write_str("\x1b[K\nno animations\x1b[K")?,
+    }
+    // Clear whatever is left below from a previous, longer frame.
+    line.write_str("\x1b[J")
+}
+
+impl<W: Write> Sink for PreviewSink<'_, W> {
+    type Error = LineError;
+
+    fn write_frame(&mut self, frame: &Frame) -> Result<(), LineError> {
+        let Self { lines, styles, stats } = self;
+        lines.write(|line| render(frame, styles, stats, line))
+    }
+}
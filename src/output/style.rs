@@ -0,0 +1,112 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use super::Segment;
+use crate::Level;
+
+/// An RGB colour, written as `#rrggbb`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Expected a colour in the form #rrggbb")]
+pub struct ParseColorError;
+
+impl Color {
+    #[must_use]
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').ok_or(ParseColorError)?;
+        if hex.len() != 6 {
+            return Err(ParseColorError);
+        }
+        let rgb = u32::from_str_radix(hex, 16).map_err(|_| ParseColorError)?;
+        Ok(Self::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+/// Colours used for segments that are not [`Level::Normal`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Palette {
+    pub warning: Color,
+    pub critical: Color,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self { warning: Color::new(0xe5, 0xc0, 0x7b), critical: Color::new(0xe0, 0x6c, 0x75) }
+    }
+}
+
+impl Palette {
+    #[must_use]
+    pub fn for_level(&self, level: Level) -> Option<Color> {
+        match level {
+            Level::Normal => None,
+            Level::Warning => Some(self.warning),
+            Level::Critical => Some(self.critical),
+        }
+    }
+}
+
+/// How a single metric is presented by outputs that support it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Style {
+    pub color: Option<Color>,
+    /// Shell command to run when the segment is clicked.
+    pub action: Option<String>,
+}
+
+/// Per-metric [`Style`]s plus the [`Palette`] for warnings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Styles {
+    pub palette: Palette,
+    metrics: Vec<(&'static str, Style)>,
+}
+
+impl Styles {
+    pub fn set(&mut self, name: &'static str, style: Style) {
+        match self.metrics.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, s)) => *s = style,
+            None => self.metrics.push((name, style)),
+        }
+    }
+
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Style> {
+        self.metrics.iter().find(|(n, _)| *n == name).map(|(_, style)| style)
+    }
+
+    /// Colour for `segment`. A warning colour takes precedence over the
+    /// metric's own one.
+    #[must_use]
+    pub fn color(&self, segment: &Segment<'_>) -> Option<Color> {
+        self.palette
+            .for_level(segment.level)
+            .or_else(|| self.get(segment.name).and_then(|style| style.color))
+    }
+
+    #[must_use]
+    pub fn action(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(|style| style.action.as_deref())
+    }
+}
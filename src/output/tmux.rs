@@ -0,0 +1,52 @@
+use std::{
+    fmt::{self, Write as _},
+    io::Write,
+};
+
+use super::{
+    line::{write_escaped, Line, LineError, LineWriter, Markup},
+    Frame, Sink, Styles,
+};
+
+/// Prints the bar with tmux `#[fg=...]` styles, for `status-right`. tmux
+/// shows the last line of a `#()` job, so it works both for a single snapshot
+/// and for a long-lived job.
+#[derive(Debug)]
+pub struct TmuxSink<W> {
+    lines: LineWriter<W>,
+    styles: Styles,
+}
+
+impl<W: Write> TmuxSink<W> {
+    pub fn new(writer: W, styles: Styles) -> Self {
+        Self { lines: LineWriter::new(writer), styles }
+    }
+}
+
+fn render(frame: &Frame, styles: &Styles, line: &mut Line) -> fmt::Result {
+    for (i, segment) in frame.segments().enumerate() {
+        if i != 0 {
+            line.write_str(frame.separator())?;
+        }
+        // `##` is a literal `#`, which would otherwise start a style or a
+        // `#()` command.
+        match styles.color(&segment) {
+            Some(color) => {
+                write!(line, "#[fg={color}]")?;
+                write_escaped(line, segment.text, Markup::Double('#'))?;
+                line.write_str("#[default]")?;
+            }
+            None => write_escaped(line, segment.text, Markup::Double('#'))?,
+        }
+    }
+    Ok(())
+}
+
+impl<W: Write> Sink for TmuxSink<W> {
+    type Error = LineError;
+
+    fn write_frame(&mut self, frame: &Frame) -> Result<(), LineError> {
+        let Self { lines, styles } = self;
+        lines.write(|line| render(frame, styles, line))
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    fmt::{self, Write as _},
+    io::Write,
+};
+
+use super::{
+    line::{write_escaped, Line, LineError, LineWriter, Markup},
+    Frame, Segment, Sink,
+};
+
+/// Prints Waybar custom module JSON (`"return-type": "json"`), one object per
+/// line and only when it changes.
+#[derive(Debug)]
+pub struct WaybarSink<W> {
+    lines: LineWriter<W>,
+    metric: Option<&'static str>,
+}
+
+impl<W: Write> WaybarSink<W> {
+    /// Shows a single metric, or the whole bar if `metric` is `None`.
+    pub fn new(writer: W, metric: Option<&'static str>) -> Self {
+        Self { lines: LineWriter::new(writer), metric }
+    }
+}
+
+fn render(frame: &Frame, metric: Option<&str>, line: &mut Line) -> fmt::Result {
+    match metric {
+        Some(name) => match frame.segment(name) {
+            Some(Segment { text, tooltip, percentage, level, .. }) => {
+                line.write_str(r#"{"text":""#)?;
+                write_escaped(&mut Json(line), text, Markup::Pango)?;
+                line.write_str(r#"","tooltip":""#)?;
+                write_escaped(&mut Json(line), tooltip, Markup::Pango)?;
+                write!(line, r#"","class":"{}""#, level.as_str())?;
+                if let Some(percentage) = percentage {
+                    write!(line, r#","percentage":{percentage}"#)?;
+                }
+                line.write_str("}")
+            }
+            None => line.write_str(r#"{"text":""}"#),
+        },
+        None => {
+            line.write_str(r#"{"text":""#)?;
+            write_escaped(&mut Json(line), frame.joined(), Markup::Pango)?;
+            line.write_str(r#"","tooltip":""#)?;
+            for (i, segment) in frame.segments().enumerate() {
+                if i != 0 {
+                    line.write_str("\\n")?;
+                }
+                write_escaped(&mut Json(line), segment.tooltip, Markup::Pango)?;
+            }
+            write!(line, r#"","class":"{}"}}"#, frame.level().as_str())
+        }
+    }
+}
+
+impl<W: Write> Sink for WaybarSink<W> {
+    type Error = LineError;
+
+    fn write_frame(&mut self, frame: &Frame) -> Result<(), LineError> {
+        let Self { lines, metric } = self;
+        lines.write(|line| render(frame, *metric, line))
+    }
+}
+
+/// Escapes what is written for use inside a JSON string literal.
+struct Json<'a>(&'a mut Line);
+
+impl fmt::Write for Json<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            match c {
+                '"' => self.0.write_str("\\\"")?,
+                '\\' => self.0.write_str("\\\\")?,
+                '\n' => self.0.write_str("\\n")?,
+                c if c.is_control() => write!(self.0, "\\u{:04x}", c as u32)?,
+                c => self.0.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
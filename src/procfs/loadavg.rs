@@ -0,0 +1,56 @@
+//! Parser for `/proc/loadavg`, see `proc_loadavg(5)`.
+
+use std::{
+    num::{ParseFloatError, ParseIntError},
+    str::FromStr,
+};
+
+pub const PATH: &str = "/proc/loadavg";
+
+#[derive(thiserror::Error, Debug)]
+pub enum LoadAvgError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Error parsing a load: {0}")]
+    ParseFloat(#[from] ParseFloatError),
+    #[error("Error parsing a number: {0}")]
+    ParseInt(#[from] ParseIntError),
+    #[error("Expected `1min 5min 15min running/total last_pid`")]
+    Malformed,
+}
+
+/// Runnable and uninterruptible tasks, averaged over 1, 5 and 15 minutes.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LoadAvg {
+    pub one: f32,
+    pub five: f32,
+    pub fifteen: f32,
+    /// Tasks currently runnable.
+    pub running: u32,
+    /// Tasks on the system, threads included.
+    pub total: u32,
+    pub last_pid: u32,
+}
+
+impl LoadAvg {
+    /// Reads and parses [`PATH`].
+    ///
+    /// # Errors
+    /// If the file cannot be read or parsed.
+    pub async fn read() -> Result<Self, LoadAvgError> {
+        tokio::fs::read_to_string(PATH).await?.parse()
+    }
+}
+
+impl FromStr for LoadAvg {
+    type Err = LoadAvgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.split_whitespace();
+        let mut next = || fields.next().ok_or(LoadAvgError::Malformed);
+        let (one, five, fifteen) = (next()?.parse()?, next()?.parse()?, next()?.parse()?);
+        let (running, total) = next()?.split_once('/').ok_or(LoadAvgError::Malformed)?;
+        let last_pid = next()?.parse()?;
+        Ok(Self { one, five, fifteen, running: running.parse()?, total: total.parse()?, last_pid })
+    }
+}
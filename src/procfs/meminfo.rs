@@ -0,0 +1,82 @@
+//! Parser for `/proc/meminfo`, see `proc_meminfo(5)`.
+
+use std::{num::ParseIntError, str::FromStr};
+
+pub const PATH: &str = "/proc/meminfo";
+
+#[derive(thiserror::Error, Debug)]
+pub enum MemInfoError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Error parsing a number: {0}")]
+    ParseInt(#[from] ParseIntError),
+    #[error("Missing the `{0}` line")]
+    Missing(&'static str),
+}
+
+/// The lines of `/proc/meminfo` the metrics use, in KiB.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MemInfo {
+    pub total: u64,
+    pub free: u64,
+    /// Estimate of what can be allocated without swapping, caches included.
+    pub available: u64,
+    pub buffers: u64,
+    pub cached: u64,
+    /// Shared memory and tmpfs, counted in `cached` but not reclaimable.
+    pub shmem: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+}
+
+impl MemInfo {
+    /// Reads and parses [`PATH`].
+    ///
+    /// # Errors
+    /// If the file cannot be read or parsed.
+    pub async fn read() -> Result<Self, MemInfoError> {
+        tokio::fs::read_to_string(PATH).await?.parse()
+    }
+
+    /// Memory in use, `total` minus `available`.
+    #[must_use]
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.available)
+    }
+
+    #[must_use]
+    pub fn swap_used(&self) -> u64 {
+        self.swap_total.saturating_sub(self.swap_free)
+    }
+}
+
+impl FromStr for MemInfo {
+    type Err = MemInfoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut info = MemInfo::default();
+        let (mut total, mut available) = (None, None);
+        for line in s.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let field = match key {
+                "MemTotal" => total.insert(0),
+                "MemFree" => &mut info.free,
+                "MemAvailable" => available.insert(0),
+                "Buffers" => &mut info.buffers,
+                "Cached" => &mut info.cached,
+                "Shmem" => &mut info.shmem,
+                "SwapTotal" => &mut info.swap_total,
+                "SwapFree" => &mut info.swap_free,
+                _ => continue,
+            };
+            let value = value.trim();
+            *field = value.strip_suffix(" kB").unwrap_or(value).parse()?;
+        }
+        info.total = total.ok_or(MemInfoError::Missing("MemTotal"))?;
+        // Only kernels older than 3.14 lack it.
+        info.available = available.ok_or(MemInfoError::Missing("MemAvailable"))?;
+        Ok(info)
+    }
+}
@@ -0,0 +1,185 @@
+//! Parser for the Pressure Stall Information in `/proc/pressure`, see
+//! `Documentation/accounting/psi.rst` in the kernel.
+
+use std::{
+    fs::File,
+    io::Write as _,
+    num::{ParseFloatError, ParseIntError},
+    str::FromStr,
+    time::Duration,
+};
+
+pub const DIR: &str = "/proc/pressure";
+
+#[derive(thiserror::Error, Debug)]
+pub enum PressureError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Error parsing an average: {0}")]
+    ParseFloat(#[from] ParseFloatError),
+    #[error("Error parsing a total: {0}")]
+    ParseInt(#[from] ParseIntError),
+    #[error("Expected `avg10=… avg60=… avg300=… total=…`")]
+    Malformed,
+    #[error("Missing the `some` line")]
+    MissingSome,
+}
+
+/// A resource tasks can stall on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Resource {
+    Cpu,
+    Memory,
+    Io,
+}
+
+impl Resource {
+    pub const ALL: [Resource; 3] = [Resource::Cpu, Resource::Memory, Resource::Io];
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Resource::Cpu => "cpu",
+            Resource::Memory => "memory",
+            Resource::Io => "io",
+        }
+    }
+
+    /// The file of the resource in [`DIR`].
+    #[must_use]
+    pub fn path(self) -> String {
+        format!("{DIR}/{}", self.as_str())
+    }
+}
+
+/// Share of the time tasks were stalled, in percent over the last 10, 60 and
+/// 300 seconds.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Stall {
+    pub avg10: f32,
+    pub avg60: f32,
+    pub avg300: f32,
+    /// Stalled time since boot, in microseconds.
+    pub total: u64,
+}
+
+/// The fields after the `some` or `full` label.
+impl FromStr for Stall {
+    type Err = PressureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut stall = Stall::default();
+        for field in s.split_whitespace() {
+            match field.split_once('=').ok_or(PressureError::Malformed)? {
+                ("avg10", value) => stall.avg10 = value.parse()?,
+                ("avg60", value) => stall.avg60 = value.parse()?,
+                ("avg300", value) => stall.avg300 = value.parse()?,
+                ("total", value) => stall.total = value.parse()?,
+                _ => {}
+            }
+        }
+        Ok(stall)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Pressure {
+    /// At least one task stalled.
+    pub some: Stall,
+    /// All non-idle tasks stalled at once. Missing for the CPU before Linux
+    /// 5.13, and always zero for it at the system level.
+    pub full: Option<Stall>,
+}
+
+impl Pressure {
+    /// Reads and parses the file of `resource`.
+    ///
+    /// # Errors
+    /// If the file cannot be read or parsed, e.g. on kernels booted without
+    /// PSI.
+    pub async fn read(resource: Resource) -> Result<Self, PressureError> {
+        tokio::fs::read_to_string(resource.path()).await?.parse()
+    }
+}
+
+impl FromStr for Pressure {
+    type Err = PressureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut some = None;
+        let mut full = None;
+        for line in s.lines() {
+            match line.split_once(' ') {
+                Some(("some", rest)) => some = Some(rest.parse()?),
+                Some(("full", rest)) => full = Some(rest.parse()?),
+                _ => {}
+            }
+        }
+        Ok(Self { some: some.ok_or(PressureError::MissingSome)?, full })
+    }
+}
+
+/// Opens the file of `resource` with a trigger that makes it readable with
+/// priority (`POLLPRI`) whenever some task stalled for `stall` within
+/// `window`. Without `CAP_SYS_RESOURCE` the window has to be a multiple of
+/// 2 seconds.
+///
+/// # Errors
+/// If the file cannot be opened or the kernel refuses the trigger.
+pub fn trigger(resource: Resource, stall: Duration, window: Duration) -> std::io::Result<File> {
+    let mut file = File::options().read(true).write(true).open(resource.path())?;
+    // The kernel parses every write on its own, so it has to be a single one,
+    // and replaces its last byte with a NUL.
+    let trigger = format!("some {} {}\0", stall.as_micros(), window.as_micros());
+    file.write_all(trigger.as_bytes())?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEMORY: &str = "\
+some avg10=1.53 avg60=0.87 avg300=0.22 total=1632455
+full avg10=0.41 avg60=0.27 avg300=0.07 total=581913
+";
+
+    #[test]
+    fn some_and_full() -> Result<(), PressureError> {
+        let pressure: Pressure = MEMORY.parse()?;
+        assert_eq!(
+            pressure,
+            Pressure {
+                some: Stall { avg10: 1.53, avg60: 0.87, avg300: 0.22, total: 1_632_455 },
+                full: Some(Stall { avg10: 0.41, avg60: 0.27, avg300: 0.07, total: 581_913 }),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cpu_without_full() -> Result<(), PressureError> {
+        // /proc/pressure/cpu before Linux 5.13.
+        let pressure: Pressure = "some avg10=12.00 avg60=5.50 avg300=1.25 total=98765\n".parse()?;
+        assert_eq!(pressure.some, Stall { avg10: 12., avg60: 5.5, avg300: 1.25, total: 98765 });
+        assert_eq!(pressure.full, None);
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_fields_ignored() -> Result<(), PressureError> {
+        let stall: Stall = "avg10=0.10 avg30=9.99 avg60=0.20 avg300=0.30 total=4".parse()?;
+        assert_eq!(stall, Stall { avg10: 0.1, avg60: 0.2, avg300: 0.3, total: 4 });
+        Ok(())
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(matches!("".parse::<Pressure>(), Err(PressureError::MissingSome)));
+        let full_only = MEMORY.lines().nth(1).unwrap_or_default();
+        assert!(matches!(full_only.parse::<Pressure>(), Err(PressureError::MissingSome)));
+        assert!(matches!("some avg10".parse::<Pressure>(), Err(PressureError::Malformed)));
+        assert!(matches!("some avg10=x".parse::<Pressure>(), Err(PressureError::ParseFloat(_))));
+        assert!(matches!("some total=1.5".parse::<Pressure>(), Err(PressureError::ParseInt(_))));
+    }
+}
@@ -0,0 +1,129 @@
+//! Parsers for `/proc/[pid]/stat` and `/proc/[pid]/status`, see `proc_pid_stat(5)`
+//! and `proc_pid_status(5)`.
+
+use std::{num::ParseIntError, path::Path, str::FromStr};
+
+pub const DIR: &str = "/proc";
+
+pub type Comm = heapless::String<16>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ProcessError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Error parsing a number: {0}")]
+    ParseInt(#[from] ParseIntError),
+    #[error("Malformed stat line")]
+    Malformed,
+}
+
+/// The fields of `/proc/[pid]/stat` the metrics use.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ProcessStat {
+    pub pid: u32,
+    /// Executable name, truncated to 15 bytes by the kernel.
+    pub comm: Comm,
+    pub state: char,
+    /// Time scheduled in user mode, in `USER_HZ` ticks.
+    pub utime: u64,
+    /// Time scheduled in kernel mode, in `USER_HZ` ticks.
+    pub stime: u64,
+    /// Time the process started after boot, in `USER_HZ` ticks. Tells apart
+    /// processes that reused a pid.
+    pub starttime: u64,
+}
+
+impl ProcessStat {
+    /// Reads and parses the stat file of `pid`. Blocks, so that scanning all
+    /// processes costs one task rather than one per file.
+    ///
+    /// # Errors
+    /// If the file cannot be read or parsed, e.g. when the process exited.
+    pub fn read(pid: u32) -> Result<Self, ProcessError> {
+        std::fs::read_to_string(Path::new(DIR).join(pid.to_string()).join("stat"))?.parse()
+    }
+
+    #[must_use]
+    pub fn ticks(&self) -> u64 {
+        self.utime + self.stime
+    }
+}
+
+impl FromStr for ProcessStat {
+    type Err = ProcessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The name may contain spaces and parentheses, only the last `)` ends it.
+        let (pid, rest) = s.split_once(" (").ok_or(ProcessError::Malformed)?;
+        let (comm, rest) = rest.rsplit_once(") ").ok_or(ProcessError::Malformed)?;
+        // Fields from the third on, `state` first.
+        let fields: heapless::Vec<&str, 20> = rest.split_whitespace().take(20).collect();
+        let field = |number: usize| fields.get(number - 3).ok_or(ProcessError::Malformed);
+        let mut truncated = Comm::new();
+        for c in comm.chars() {
+            if truncated.push(c).is_err() {
+                break;
+            }
+        }
+        Ok(Self {
+            pid: pid.trim().parse()?,
+            comm: truncated,
+            state: field(3)?.chars().next().ok_or(ProcessError::Malformed)?,
+            utime: field(14)?.parse()?,
+            stime: field(15)?.parse()?,
+            starttime: field(22)?.parse()?,
+        })
+    }
+}
+
+/// The fields of `/proc/[pid]/status` the metrics use.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ProcessStatus {
+    /// Resident memory in KiB, `None` for kernel threads.
+    pub rss: Option<u64>,
+    pub threads: u32,
+}
+
+impl ProcessStatus {
+    /// Reads and parses the status file of `pid`. Blocks, like
+    /// [`ProcessStat::read`].
+    ///
+    /// # Errors
+    /// If the file cannot be read or parsed, e.g. when the process exited.
+    pub fn read(pid: u32) -> Result<Self, ProcessError> {
+        std::fs::read_to_string(Path::new(DIR).join(pid.to_string()).join("status"))?.parse()
+    }
+}
+
+impl FromStr for ProcessStatus {
+    type Err = ProcessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut status = ProcessStatus::default();
+        for line in s.lines() {
+            match line.split_once(':') {
+                Some(("VmRSS", value)) => {
+                    let kib = value.trim().strip_suffix("kB").ok_or(ProcessError::Malformed)?;
+                    status.rss = Some(kib.trim().parse()?);
+                }
+                Some(("Threads", value)) => status.threads = value.trim().parse()?,
+                _ => {}
+            }
+        }
+        Ok(status)
+    }
+}
+
+/// Pids of the processes in [`DIR`], threads excluded. Blocks.
+///
+/// # Errors
+/// If [`DIR`] cannot be listed.
+pub fn pids() -> Result<Vec<u32>, std::io::Error> {
+    let mut pids = Vec::new();
+    for entry in std::fs::read_dir(DIR)? {
+        if let Some(pid) = entry?.file_name().to_str().and_then(|name| name.parse().ok()) {
+            pids.push(pid);
+        }
+    }
+    Ok(pids)
+}
@@ -0,0 +1,139 @@
+//! Default routes from `/proc/net/route` and `/proc/net/ipv6_route`, the IPv4
+//! and IPv6 routing tables of the kernel.
+
+use std::{io::ErrorKind, num::ParseIntError};
+
+use crate::sysfs::net::Name;
+
+pub const PATH: &str = "/proc/net/route";
+pub const IPV6_PATH: &str = "/proc/net/ipv6_route";
+
+/// `RTF_UP` and `RTF_REJECT` of `linux/route.h`.
+const RTF_UP: u32 = 0x1;
+const RTF_REJECT: u32 = 0x200;
+
+#[derive(thiserror::Error, Debug)]
+pub enum RouteError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Error parsing a number: {0}")]
+    ParseInt(#[from] ParseIntError),
+    #[error("Malformed route")]
+    Malformed,
+}
+
+/// A route to anywhere.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DefaultRoute {
+    pub interface: Name,
+    /// The route with the lowest metric wins.
+    pub metric: u32,
+}
+
+impl DefaultRoute {
+    /// The default routes of both tables, the preferred first. Without IPv6
+    /// support there is only the IPv4 table.
+    ///
+    /// # Errors
+    /// If a table cannot be read or parsed.
+    pub async fn read() -> Result<Vec<Self>, RouteError> {
+        let mut routes = ipv4(&tokio::fs::read_to_string(PATH).await?)?;
+        match tokio::fs::read_to_string(IPV6_PATH).await {
+            Ok(table) => routes.extend(ipv6(&table)?),
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+        routes.sort_by_key(|route| route.metric);
+        Ok(routes)
+    }
+
+    fn new(interface: &str, metric: u32) -> Result<Self, RouteError> {
+        Ok(Self {
+            interface: Name::try_from(interface).map_err(|()| RouteError::Malformed)?,
+            metric,
+        })
+    }
+}
+
+/// `Iface Destination Gateway Flags RefCnt Use Metric Mask ...`, after a line
+/// of column names. Addresses and flags are in hex.
+fn ipv4(table: &str) -> Result<Vec<DefaultRoute>, RouteError> {
+    let mut routes = Vec::new();
+    for line in table.lines().skip(1) {
+        let fields: heapless::Vec<&str, 8> = line.split_whitespace().take(8).collect();
+        let [interface, destination, _, flags, _, _, metric, mask] = *fields.as_slice() else {
+            return Err(RouteError::Malformed);
+        };
+        let flags = u32::from_str_radix(flags, 16)?;
+        if destination == "00000000" && mask == "00000000" && flags & RTF_UP != 0 {
+            routes.push(DefaultRoute::new(interface, metric.parse()?)?);
+        }
+    }
+    Ok(routes)
+}
+
+/// `destination prefix source prefix next_hop metric refcnt use flags iface`,
+/// all in hex but the interface.
+fn ipv6(table: &str) -> Result<Vec<DefaultRoute>, RouteError> {
+    let mut routes = Vec::new();
+    for line in table.lines() {
+        let fields: heapless::Vec<&str, 10> = line.split_whitespace().take(10).collect();
+        let [destination, prefix, _, _, _, metric, _, _, flags, interface] = *fields.as_slice()
+        else {
+            return Err(RouteError::Malformed);
+        };
+        let flags = u32::from_str_radix(flags, 16)?;
+        // Without a connection, `lo` holds an unreachable default route.
+        let default = prefix == "00" && destination.bytes().all(|digit| digit == b'0');
+        if default && flags & RTF_UP != 0 && flags & RTF_REJECT == 0 {
+            routes.push(DefaultRoute::new(interface, u32::from_str_radix(metric, 16)?)?);
+        }
+    }
+    Ok(routes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ipv4_default() -> Result<(), RouteError> {
+        let table = "\
+Iface	Destination	Gateway 	Flags	RefCnt	Use	Metric	Mask		MTU	Window	IRTT
+wlan0	00000000	0100A8C0	0003	0	0	600	00000000	0	0	0
+wlan0	0000A8C0	00000000	0001	0	0	600	00FFFFFF	0	0	0
+eth0	00000000	0101A8C0	0002	0	0	100	00000000	0	0	0
+";
+        // `eth0` is down.
+        assert_eq!(ipv4(table)?, [DefaultRoute::new("wlan0", 600)?]);
+        Ok(())
+    }
+
+    #[test]
+    fn ipv6_lo_reject() -> Result<(), RouteError> {
+        // Offline: only the unreachable default route on `lo`.
+        let offline = "\
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 00000000000000000000000000000000 ffffffff 00000001 00000000 00200200       lo
+00000000000000000000000000000001 80 00000000000000000000000000000000 00 00000000000000000000000000000000 00000000 00000002 00000000 80200001       lo
+";
+        assert_eq!(ipv6(offline)?, []);
+
+        let online = format!(
+            "{offline}\
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 fe80000000000000021122fffe334455 00000258 00000001 00000000 00450003    wlan0
+fe800000000000000000000000000000 40 00000000000000000000000000000000 00 00000000000000000000000000000000 00000100 00000001 00000000 00000001    wlan0
+"
+        );
+        assert_eq!(ipv6(&online)?, [DefaultRoute::new("wlan0", 0x258)?]);
+        Ok(())
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(matches!(ipv4("header\nwlan0 00000000 0001"), Err(RouteError::Malformed)));
+        assert!(matches!(ipv6("00 00 lo"), Err(RouteError::Malformed)));
+        let flags = "00000000000000000000000000000000 00 00000000000000000000000000000000 00 \
+                     00000000000000000000000000000000 00000000 00000001 00000000 0000000x lo";
+        assert!(matches!(ipv6(flags), Err(RouteError::ParseInt(_))));
+    }
+}
@@ -0,0 +1,232 @@
+//! Parser for `/proc/stat`, see `proc_stat(5)`.
+
+use std::{num::ParseIntError, str::FromStr};
+
+pub const PATH: &str = "/proc/stat";
+
+#[derive(thiserror::Error, Debug)]
+pub enum StatError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Error parsing a number: {0}")]
+    ParseInt(#[from] ParseIntError),
+    #[error("Too few fields in a cpu line")]
+    MalformedCpu,
+    #[error("Missing the `{0}` line")]
+    Missing(&'static str),
+}
+
+/// Time a CPU spent in each state since boot, in `USER_HZ` ticks.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CpuTimes {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    /// Taken by the hypervisor for other guests.
+    pub steal: u64,
+    /// Running guests, already included in `user`.
+    pub guest: u64,
+    /// Running niced guests, already included in `nice`.
+    pub guest_nice: u64,
+}
+
+impl CpuTimes {
+    /// Time spent waiting, idle or for IO.
+    #[must_use]
+    pub fn idle_time(&self) -> u64 {
+        self.idle + self.iowait
+    }
+
+    /// All the time, without counting guests twice.
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.user
+            + self.nice
+            + self.system
+            + self.idle
+            + self.iowait
+            + self.irq
+            + self.softirq
+            + self.steal
+    }
+
+    /// Percentage of the time since `earlier` the CPU was busy, `None` if no
+    /// time passed.
+    #[must_use]
+    pub fn usage_since(&self, earlier: &Self) -> Option<u8> {
+        let total = self.total().checked_sub(earlier.total()).filter(|&total| total > 0)?;
+        let idle = self.idle_time().saturating_sub(earlier.idle_time()).min(total);
+        Some(((total - idle) * 100 / total) as u8)
+    }
+}
+
+/// The fields after the `cpu` or `cpuN` label. Fields newer kernels added are
+/// zero on older ones.
+impl FromStr for CpuTimes {
+    type Err = StatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = [0; 10];
+        let mut count = 0;
+        for (field, value) in fields.iter_mut().zip(s.split_whitespace()) {
+            *field = value.parse()?;
+            count += 1;
+        }
+        if count < 4 {
+            return Err(StatError::MalformedCpu);
+        }
+        let [user, nice, system, idle, iowait, irq, softirq, steal, guest, guest_nice] = fields;
+        Ok(Self { user, nice, system, idle, iowait, irq, softirq, steal, guest, guest_nice })
+    }
+}
+
+/// A `cpuN` line.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Core {
+    /// The `N` of `cpuN`.
+    pub index: u32,
+    pub times: CpuTimes,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stat {
+    /// All CPUs together.
+    pub cpu: CpuTimes,
+    /// Every `cpuN` line, in order. Offline CPUs are missing, so the indices
+    /// may skip numbers.
+    pub cores: Vec<Core>,
+    /// Context switches since boot.
+    pub context_switches: u64,
+    /// Processes and threads created since boot.
+    pub processes: u64,
+    pub procs_running: u32,
+    pub procs_blocked: u32,
+}
+
+impl Stat {
+    /// Reads and parses [`PATH`].
+    ///
+    /// # Errors
+    /// If the file cannot be read or parsed.
+    pub async fn read() -> Result<Self, StatError> {
+        tokio::fs::read_to_string(PATH).await?.parse()
+    }
+}
+
+impl FromStr for Stat {
+    type Err = StatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut stat = Stat::default();
+        let mut cpu = None;
+        let mut context_switches = None;
+
+        for line in s.lines() {
+            let (key, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "cpu" => cpu = Some(rest.parse()?),
+                key if key.starts_with("cpu") => stat
+                    .cores
+                    .push(Core { index: key["cpu".len()..].parse()?, times: rest.parse()? }),
+                "ctxt" => context_switches = Some(rest.trim().parse()?),
+                "processes" => stat.processes = rest.trim().parse()?,
+                "procs_running" => stat.procs_running = rest.trim().parse()?,
+                "procs_blocked" => stat.procs_blocked = rest.trim().parse()?,
+                _ => {}
+            }
+        }
+
+        stat.cpu = cpu.ok_or(StatError::Missing("cpu"))?;
+        stat.context_switches = context_switches.ok_or(StatError::Missing("ctxt"))?;
+        Ok(stat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `/proc/stat` of a 4-core laptop with `cpu2` offline.
+    const STAT: &str = "\
+cpu  10132153 290696 3084719 46828483 16683 0 25195 0 0 0
+cpu0 1393280 32966 572056 13074540 1830 0 13476 0 0 0
+cpu1 1335398 35542 447993 13114186 4564 0 2906 0 0 0
+cpu3 1257398 27836 461931 13190418 2958 0 3207 0 0 0
+intr 1462898 18 4 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+ctxt 1990473
+btime 1062191376
+processes 2915
+procs_running 1
+procs_blocked 0
+softirq 183433 0 21755 12 39 0 0 0 4 0 0
+";
+
+    #[test]
+    fn aggregate() -> Result<(), StatError> {
+        let stat: Stat = STAT.parse()?;
+        assert_eq!(
+            stat.cpu,
+            CpuTimes {
+                user: 10_132_153,
+                nice: 290_696,
+                system: 3_084_719,
+                idle: 46_828_483,
+                iowait: 16_683,
+                irq: 0,
+                softirq: 25_195,
+                ..CpuTimes::default()
+            }
+        );
+        assert_eq!((stat.processes, stat.procs_running, stat.procs_blocked), (2915, 1, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn cores() -> Result<(), StatError> {
+        let stat: Stat = STAT.parse()?;
+        let indices: Vec<_> = stat.cores.iter().map(|core| core.index).collect();
+        assert_eq!(indices, [0, 1, 3]);
+        assert_eq!(stat.cores[2].times.user, 1_257_398);
+        assert_eq!(stat.cores[2].times.idle_time(), 13_190_418 + 2958);
+        Ok(())
+    }
+
+    #[test]
+    fn context_switches() -> Result<(), StatError> {
+        assert_eq!(STAT.parse::<Stat>()?.context_switches, 1_990_473);
+        let without = STAT.replace("ctxt 1990473\n", "");
+        assert!(matches!(without.parse::<Stat>(), Err(StatError::Missing("ctxt"))));
+        Ok(())
+    }
+
+    #[test]
+    fn older_kernel() -> Result<(), StatError> {
+        // Before Linux 2.6.11, only the first 4 to 7 fields.
+        let times: CpuTimes = "4705 356 584 3699".parse()?;
+        assert_eq!((times.user, times.idle, times.steal), (4705, 3699, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(matches!("1 2 3".parse::<CpuTimes>(), Err(StatError::MalformedCpu)));
+        assert!(matches!("1 2 x 4".parse::<CpuTimes>(), Err(StatError::ParseInt(_))));
+        let short = STAT.replace("cpu1 1335398 35542 447993 13114186 4564 0 2906 0 0 0", "cpu1 7");
+        assert!(matches!(short.parse::<Stat>(), Err(StatError::MalformedCpu)));
+        let label = STAT.replace("cpu3 ", "cpux ");
+        assert!(matches!(label.parse::<Stat>(), Err(StatError::ParseInt(_))));
+        assert!(matches!("ctxt 1\n".parse::<Stat>(), Err(StatError::Missing("cpu"))));
+    }
+
+    #[test]
+    fn usage() {
+        let earlier = CpuTimes { user: 100, idle: 100, ..CpuTimes::default() };
+        let later = CpuTimes { user: 175, idle: 125, ..CpuTimes::default() };
+        assert_eq!(later.usage_since(&earlier), Some(75));
+        assert_eq!(earlier.usage_since(&earlier), None);
+    }
+}
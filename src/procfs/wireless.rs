@@ -0,0 +1,108 @@
+//! Parser for `/proc/net/wireless`, the link quality of Wi-Fi interfaces.
+
+use std::{num::ParseIntError, str::FromStr};
+
+use crate::sysfs::net::Name;
+
+pub const PATH: &str = "/proc/net/wireless";
+
+/// The best link quality, on the scale of cfg80211 drivers.
+pub const MAX_QUALITY: u8 = 70;
+
+#[derive(thiserror::Error, Debug)]
+pub enum WirelessError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Error parsing a number: {0}")]
+    ParseInt(#[from] ParseIntError),
+    #[error("Expected `interface: status link level noise ...`")]
+    Malformed,
+}
+
+/// A line of [`PATH`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Wireless {
+    pub interface: Name,
+    /// Link quality, up to [`MAX_QUALITY`].
+    pub quality: u8,
+    /// Signal level in dBm.
+    pub level: i32,
+}
+
+impl Wireless {
+    /// Reads and parses [`PATH`], which lists the wireless interfaces that are
+    /// up.
+    ///
+    /// # Errors
+    /// If the file cannot be read or parsed.
+    pub async fn read() -> Result<Vec<Self>, WirelessError> {
+        let table = tokio::fs::read_to_string(PATH).await?;
+        // Two lines of column names.
+        table.lines().skip(2).map(str::parse).collect()
+    }
+
+    /// The link quality as a percentage.
+    #[must_use]
+    pub fn signal(&self) -> u8 {
+        (u32::from(self.quality) * 100 / u32::from(MAX_QUALITY)).min(100) as u8
+    }
+}
+
+impl FromStr for Wireless {
+    type Err = WirelessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (interface, rest) = s.split_once(':').ok_or(WirelessError::Malformed)?;
+        let mut fields = rest.split_whitespace();
+        let mut next = || fields.next().ok_or(WirelessError::Malformed);
+        let _status = next()?;
+        // Values updated since the last read end with a `.`.
+        let quality = next()?.trim_end_matches('.').parse()?;
+        let level = next()?.trim_end_matches('.').parse()?;
+        Ok(Self {
+            interface: Name::try_from(interface.trim()).map_err(|()| WirelessError::Malformed)?,
+            quality,
+            level,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn updated_values() -> Result<(), WirelessError> {
+        // A trailing `.` marks values updated since the last read.
+        let wireless: Wireless =
+            "wlp2s0: 0000   54.  -56.  -256        0      0      0      0      0        0"
+                .parse()?;
+        assert_eq!(
+            (wireless.interface.as_str(), wireless.quality, wireless.level),
+            ("wlp2s0", 54, -56)
+        );
+        assert_eq!(wireless.signal(), 77);
+        Ok(())
+    }
+
+    #[test]
+    fn stale_values() -> Result<(), WirelessError> {
+        let wireless: Wireless = "  wlan0: 0000   70   -40   -256  0 0 0 0 0 0".parse()?;
+        assert_eq!(
+            (wireless.interface.as_str(), wireless.quality, wireless.level),
+            ("wlan0", 70, -40)
+        );
+        assert_eq!(wireless.signal(), 100);
+        Ok(())
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(matches!("wlan0 0000 54. -56.".parse::<Wireless>(), Err(WirelessError::Malformed)));
+        assert!(matches!("wlan0: 0000 54.".parse::<Wireless>(), Err(WirelessError::Malformed)));
+        assert!(matches!(
+            "wlan0: 0000 5x. -56.".parse::<Wireless>(),
+            Err(WirelessError::ParseInt(_))
+        ));
+    }
+}
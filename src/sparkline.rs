@@ -0,0 +1,103 @@
+use std::{
+    fmt::{self, Display, Formatter, Write},
+    str::FromStr,
+};
+
+use crate::history::History;
+
+const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Widest sparkline that can be drawn.
+pub const MAX_WIDTH: usize = 64;
+
+/// How values are mapped to the height of the blocks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scale {
+    /// Between the two values, e.g. `0..100` for percentages.
+    Fixed(f64, f64),
+    /// Between zero and the largest value shown.
+    Auto,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Expected `auto` or a range like `0..100`")]
+pub struct ParseScaleError;
+
+impl FromStr for Scale {
+    type Err = ParseScaleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "auto" {
+            return Ok(Scale::Auto);
+        }
+        let (min, max) = s.split_once("..").ok_or(ParseScaleError)?;
+        let min = min.trim().parse().map_err(|_| ParseScaleError)?;
+        let max = max.trim().parse().map_err(|_| ParseScaleError)?;
+        if max > min {
+            Ok(Scale::Fixed(min, max))
+        } else {
+            Err(ParseScaleError)
+        }
+    }
+}
+
+/// The block glyph for `level`, clamped to `0..=1`.
+#[must_use]
+pub fn block(level: f64) -> char {
+    BLOCKS[(level.clamp(0., 1.) * (BLOCKS.len() - 1) as f64).round() as usize]
+}
+
+/// Graph of the most recent values of a [`History`] drawn with block glyphs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sparkline {
+    width: usize,
+    scale: Scale,
+}
+
+impl Sparkline {
+    /// `width` is clamped to [`MAX_WIDTH`].
+    #[must_use]
+    pub fn new(width: usize, scale: Scale) -> Self {
+        Self { width: width.min(MAX_WIDTH), scale }
+    }
+
+    /// One block per sample, the newest on the right. Missing samples are
+    /// drawn as spaces so the width stays the same.
+    #[must_use]
+    pub fn render<'a, const N: usize>(&'a self, history: &'a History<N>) -> impl Display + 'a {
+        Rendered { sparkline: self, history }
+    }
+}
+
+struct Rendered<'a, const N: usize> {
+    sparkline: &'a Sparkline,
+    history: &'a History<N>,
+}
+
+impl<const N: usize> Display for Rendered<'_, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Sparkline { width, scale } = *self.sparkline;
+
+        let mut values = heapless::Vec::<f64, MAX_WIDTH>::new();
+        self.history.with_samples(|samples| {
+            for sample in samples.rev().take(width) {
+                _ = values.push(sample.value);
+            }
+        });
+        values.reverse();
+
+        let (min, max) = match scale {
+            Scale::Fixed(min, max) => (min, max),
+            Scale::Auto => (0., values.iter().copied().fold(0., f64::max)),
+        };
+
+        for _ in values.len()..width {
+            f.write_char(' ')?;
+        }
+        for value in values {
+            let level = if max > min { (value - min) / (max - min) } else { 0. };
+            f.write_char(block(level))?;
+        }
+        Ok(())
+    }
+}
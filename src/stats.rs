@@ -0,0 +1,53 @@
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+/// What happened during the latest update of a metric.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateStats {
+    pub name: &'static str,
+    pub interval: Duration,
+    pub last_update: Instant,
+    pub latency: Duration,
+    pub last_error: Option<String>,
+}
+
+/// [`UpdateStats`] of every metric that has been updated so far.
+#[derive(Debug, Default)]
+pub struct Stats(RefCell<Vec<UpdateStats>>);
+
+impl Stats {
+    /// Records an update of `name` that started at `started` and has just
+    /// finished. The last error is kept until the next successful update.
+    pub fn record(
+        &self,
+        name: &'static str,
+        interval: Duration,
+        started: Instant,
+        error: Option<&dyn Display>,
+    ) {
+        let mut stats = self.0.borrow_mut();
+        let i = stats.iter().position(|stats| stats.name == name).unwrap_or_else(|| {
+            stats.push(UpdateStats {
+                name,
+                interval,
+                last_update: started,
+                latency: Duration::ZERO,
+                last_error: None,
+            });
+            stats.len() - 1
+        });
+        let stats = &mut stats[i];
+        stats.interval = interval;
+        stats.last_update = started;
+        stats.latency = started.elapsed();
+        stats.last_error = error.map(ToString::to_string);
+    }
+
+    /// Calls `f` for each metric, in the order they were first updated.
+    pub fn for_each(&self, mut f: impl FnMut(&UpdateStats)) {
+        self.0.borrow().iter().for_each(&mut f);
+    }
+}
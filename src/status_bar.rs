@@ -0,0 +1,423 @@
+//! [`StatusBar`] wires metrics, their update loops and a [`Sink`] together,
+//! so a custom bar is a list of metrics and a choice of output.
+
+use std::{
+    future::Future,
+    ops::Add,
+    pin::Pin,
+    task::Poll,
+    time::{Duration, Instant},
+};
+
+use frunk::hlist::{HCons, HList, HNil};
+use tokio::{sync::Notify, task::LocalSet};
+
+use crate::{
+    alert::Alerts,
+    output::{
+        frame::{DEFAULT_SEPARATOR, MAX_SEGMENTS},
+        Frame, Sink,
+    },
+    stats::Stats,
+    Metric,
+};
+
+/// The frame rate is adaptive: a frame is drawn when a metric updates or its
+/// animation asks for one, but by default never more often than 71 times a
+/// second, and never less than once a `MAX_PERIOD`.
+pub const DEFAULT_MIN_PERIOD: Duration = Duration::from_nanos(1_000_000_000 / 71);
+pub const MAX_PERIOD: Duration = Duration::from_secs(1);
+
+#[derive(thiserror::Error, Debug)]
+pub enum RunError<E> {
+    #[error("Failed to build the tokio runtime: {0}")]
+    Runtime(std::io::Error),
+    #[error("{0}")]
+    Sink(E),
+}
+
+/// A metric of a [`StatusBar`], and how often it is updated if at all.
+/// Disabled metrics are neither updated nor shown.
+#[derive(Debug, Clone, Copy)]
+pub struct Scheduled<M> {
+    metric: M,
+    interval: Option<Duration>,
+    enabled: bool,
+}
+
+/// What every update loop shares.
+#[derive(Debug, Clone, Copy)]
+pub struct Updater<'a> {
+    /// Metrics other than `only` are not updated when it is set.
+    only: Option<&'static str>,
+    stats: Option<&'a Stats>,
+    alerts: Option<&'a Alerts>,
+    redraw: &'a Notify,
+}
+
+impl Updater<'_> {
+    /// "Spawns" a loop that updates a metric every `interval` duration, and
+    /// whenever [`Metric::changed`] resolves in between.
+    async fn metric_interval<M: Metric>(self, interval: Duration, metric: &M) {
+        let name = metric.name();
+        if self.only.is_some_and(|only| only != name) {
+            return;
+        }
+        let period = interval;
+        let mut interval = tokio::time::interval(interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                () = metric.changed() => {}
+            }
+            let started = Instant::now();
+            let result = metric.update().await;
+            if let Err(err) = &result {
+                log::error!("Error in {name}: {err}");
+            }
+            if let Some(stats) = self.stats {
+                stats.record(name, period, started, result.as_ref().err().map(|err| err as _));
+            }
+            if let (Ok(()), Some(alerts)) = (&result, self.alerts) {
+                alerts.check(metric);
+            }
+            self.redraw.notify_one();
+        }
+    }
+}
+
+/// The [`Scheduled`] metrics of a [`StatusBar`], in display order: an `HList`
+/// built by the [`StatusBar`] methods, or a `Vec` for lists only known at
+/// runtime, see [`StatusBar::dynamic`].
+pub trait Metrics {
+    /// Fails to compile if there are more metrics than a frame holds, when the
+    /// number is known at compile time.
+    const FITS: () = ();
+
+    fn names(&self, names: &mut heapless::Vec<&'static str, MAX_SEGMENTS>);
+    fn render(&self, frame: &mut Frame);
+    /// Runs the update loops of all metrics. Only returns if no metric is
+    /// updated.
+    fn update<'a>(&'a self, updater: Updater<'a>) -> impl Future<Output = ()> + 'a;
+}
+
+impl Metrics for HNil {
+    fn names(&self, _: &mut heapless::Vec<&'static str, MAX_SEGMENTS>) {}
+
+    fn render(&self, _: &mut Frame) {}
+
+    fn update<'a>(&'a self, _: Updater<'a>) -> impl Future<Output = ()> + 'a {
+        std::future::ready(())
+    }
+}
+
+impl<M: Metric, T: Metrics + HList> Metrics for HCons<Scheduled<M>, T> {
+    const FITS: () = assert!(Self::LEN <= MAX_SEGMENTS, "Too many metrics for a frame");
+
+    fn names(&self, names: &mut heapless::Vec<&'static str, MAX_SEGMENTS>) {
+        // `StatusBar` checks the number of metrics at compile time.
+        if self.head.enabled {
+            _ = names.push(self.head.metric.name());
+        }
+        self.tail.names(names);
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        if self.head.enabled {
+            if let Err(err) = frame.push(&self.head.metric) {
+                log::error!("Error while writing metric {}: {err}", self.head.metric.name());
+            }
+        }
+        self.tail.render(frame);
+    }
+
+    fn update<'a>(&'a self, updater: Updater<'a>) -> impl Future<Output = ()> + 'a {
+        async move {
+            let head = async {
+                if let (Some(interval), true) = (self.head.interval, self.head.enabled) {
+                    updater.metric_interval(interval, &self.head.metric).await;
+                }
+            };
+            tokio::join!(head, self.tail.update(updater));
+        }
+    }
+}
+
+/// Metrics past [`MAX_SEGMENTS`] are not shown.
+impl<M: Metric> Metrics for Vec<Scheduled<M>> {
+    fn names(&self, names: &mut heapless::Vec<&'static str, MAX_SEGMENTS>) {
+        for scheduled in self.iter().filter(|scheduled| scheduled.enabled) {
+            _ = names.push(scheduled.metric.name());
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        for scheduled in self.iter().filter(|scheduled| scheduled.enabled) {
+            if let Err(err) = frame.push(&scheduled.metric) {
+                log::error!("Error while writing metric {}: {err}", scheduled.metric.name());
+            }
+        }
+    }
+
+    fn update<'a>(&'a self, updater: Updater<'a>) -> impl Future<Output = ()> + 'a {
+        let mut loops: Vec<Option<Pin<Box<dyn Future<Output = ()> + 'a>>>> = self
+            .iter()
+            .filter_map(|scheduled| match (scheduled.interval, scheduled.enabled) {
+                (Some(interval), true) => {
+                    Some(Some(Box::pin(updater.metric_interval(interval, &scheduled.metric)) as _))
+                }
+                _ => None,
+            })
+            .collect();
+        // Joins the loops, like `tokio::join!` for a number only known at runtime.
+        std::future::poll_fn(move |cx| {
+            for slot in &mut loops {
+                if slot.as_mut().is_some_and(|update| update.as_mut().poll(cx).is_ready()) {
+                    *slot = None;
+                }
+            }
+            if loops.iter().all(Option::is_none) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+    }
+}
+
+/// Builder of a status bar: add metrics with their update intervals, pick a
+/// separator, then run it with a [`Sink`].
+#[derive(Debug, Clone)]
+pub struct StatusBar<'a, L = HNil> {
+    metrics: L,
+    separator: &'static str,
+    min_period: Duration,
+    only: Option<&'static str>,
+    stats: Option<&'a Stats>,
+    alerts: Option<&'a Alerts>,
+}
+
+impl Default for StatusBar<'_> {
+    fn default() -> Self {
+        Self {
+            metrics: HNil,
+            separator: DEFAULT_SEPARATOR,
+            min_period: DEFAULT_MIN_PERIOD,
+            only: None,
+            stats: None,
+            alerts: None,
+        }
+    }
+}
+
+impl StatusBar<'_> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<M: Metric> StatusBar<'_, Vec<Scheduled<M>>> {
+    /// A bar over a list of metrics built at runtime, all of the same type.
+    /// With `M` a `Box<dyn DynMetric>`, see [`DynMetric`](crate::dynamic::DynMetric),
+    /// they may be of any type.
+    #[must_use]
+    pub fn dynamic() -> Self {
+        let StatusBar { separator, min_period, only, stats, alerts, .. } = StatusBar::new();
+        StatusBar { metrics: Vec::new(), separator, min_period, only, stats, alerts }
+    }
+
+    /// Adds `metric` to the end of the bar, updated every `interval` or never
+    /// if `None`, like [`static_metric`](StatusBar::static_metric).
+    pub fn push(&mut self, metric: M, interval: Option<Duration>) {
+        self.metrics.push(Scheduled { metric, interval, enabled: true });
+    }
+}
+
+type Pushed<L, M> = <L as Add<HCons<Scheduled<M>, HNil>>>::Output;
+
+impl<'a, L> StatusBar<'a, L> {
+    fn append<M>(self, scheduled: Scheduled<M>) -> StatusBar<'a, Pushed<L, M>>
+    where
+        L: Add<HCons<Scheduled<M>, HNil>>,
+    {
+        let Self { metrics, separator, min_period, only, stats, alerts } = self;
+        let metrics = metrics + frunk::hlist![scheduled];
+        StatusBar { metrics, separator, min_period, only, stats, alerts }
+    }
+
+    /// Adds `metric` to the end of the bar, updated every `interval`.
+    #[must_use]
+    pub fn metric<M: Metric>(self, metric: M, interval: Duration) -> StatusBar<'a, Pushed<L, M>>
+    where
+        L: Add<HCons<Scheduled<M>, HNil>>,
+    {
+        self.append(Scheduled { metric, interval: Some(interval), enabled: true })
+    }
+
+    /// Like [`metric`](Self::metric), but the metric is left out of the bar
+    /// unless `enabled`, e.g. as configured.
+    #[must_use]
+    pub fn metric_if<M: Metric>(
+        self,
+        enabled: bool,
+        metric: M,
+        interval: Duration,
+    ) -> StatusBar<'a, Pushed<L, M>>
+    where
+        L: Add<HCons<Scheduled<M>, HNil>>,
+    {
+        self.append(Scheduled { metric, interval: Some(interval), enabled })
+    }
+
+    /// Adds `metric` to the end of the bar without ever updating it, for
+    /// metrics that compute their text when displayed.
+    #[must_use]
+    pub fn static_metric<M: Metric>(self, metric: M) -> StatusBar<'a, Pushed<L, M>>
+    where
+        L: Add<HCons<Scheduled<M>, HNil>>,
+    {
+        self.append(Scheduled { metric, interval: None, enabled: true })
+    }
+
+    #[must_use]
+    pub fn separator(self, separator: &'static str) -> Self {
+        Self { separator, ..self }
+    }
+
+    /// Shortest time between two frames, at most [`MAX_PERIOD`].
+    #[must_use]
+    pub fn min_period(self, min_period: Duration) -> Self {
+        Self { min_period: min_period.min(MAX_PERIOD), ..self }
+    }
+
+    /// Only updates the metric named `only`, if set.
+    #[must_use]
+    pub fn only(self, only: Option<&'static str>) -> Self {
+        Self { only, ..self }
+    }
+
+    /// Records the updates of every metric in `stats`.
+    #[must_use]
+    pub fn stats(self, stats: &'a Stats) -> Self {
+        Self { stats: Some(stats), ..self }
+    }
+
+    /// Checks `alerts` after every successful update.
+    #[must_use]
+    pub fn alerts(self, alerts: &'a Alerts) -> Self {
+        Self { alerts: Some(alerts), ..self }
+    }
+}
+
+impl<L: Metrics> StatusBar<'_, L> {
+    /// Names of the metrics, in display order.
+    #[must_use]
+    pub fn names(&self) -> heapless::Vec<&'static str, MAX_SEGMENTS> {
+        let () = L::FITS;
+        let mut names = heapless::Vec::new();
+        self.metrics.names(&mut names);
+        names
+    }
+
+    fn updates<'a>(&'a self, redraw: &'a Notify) -> impl Future<Output = ()> + 'a {
+        let Self { only, stats, alerts, .. } = *self;
+        self.metrics.update(Updater { only, stats, alerts, redraw })
+    }
+
+    fn frame(&self) -> Frame {
+        let () = L::FITS;
+        Frame::new(self.separator)
+    }
+
+    /// Renders a frame and hands it to `sink` whenever a metric updates or the
+    /// frame asks for it, but at most once per `min_period`.
+    async fn frame_loop<S: Sink>(&self, mut sink: S, redraw: &Notify) -> S::Error {
+        let mut frame = self.frame();
+
+        loop {
+            let started = tokio::time::Instant::now();
+            frame.clear();
+            self.metrics.render(&mut frame);
+
+            // Purposefully block the executor. There are no tasks except this one.
+            if let Err(err) = sink.write_frame(&frame) {
+                return err;
+            }
+
+            let period = frame.period().unwrap_or(MAX_PERIOD).clamp(self.min_period, MAX_PERIOD);
+            tokio::select! {
+                () = tokio::time::sleep_until(started + period) => {}
+                () = redraw.notified() => tokio::time::sleep_until(started + self.min_period).await,
+            }
+        }
+    }
+
+    /// Updates the metrics and draws frames into `sink`. Returns only if the
+    /// sink fails.
+    pub async fn serve<S: Sink>(&self, sink: S) -> S::Error {
+        let redraw = Notify::new();
+        let frames = self.frame_loop(sink, &redraw);
+        tokio::pin!(frames);
+        tokio::select! {
+            err = &mut frames => err,
+            () = self.updates(&redraw) => frames.await,
+        }
+    }
+
+    /// Updates the metrics for `delay`, then hands a single frame to `sink`.
+    ///
+    /// # Errors
+    /// If the sink fails.
+    pub async fn snapshot<S: Sink>(&self, mut sink: S, delay: Duration) -> Result<(), S::Error> {
+        let redraw = Notify::new();
+        let deadline = tokio::time::sleep(delay);
+        tokio::pin!(deadline);
+        tokio::select! {
+            () = &mut deadline => {}
+            () = self.updates(&redraw) => deadline.await,
+        }
+        let mut frame = self.frame();
+        self.metrics.render(&mut frame);
+        sink.write_frame(&frame)
+    }
+
+    /// Runs [`serve`](Self::serve) on a new single-threaded runtime.
+    pub fn run<S: Sink>(&self, sink: S) -> RunError<S::Error> {
+        self.run_with(sink, async {})
+    }
+
+    /// Like [`run`](Self::run), with `task` running alongside, e.g. an
+    /// exporter. The bar keeps running if `task` returns.
+    pub fn run_with<S: Sink>(&self, sink: S, task: impl Future<Output = ()>) -> RunError<S::Error> {
+        let result = block_on(async {
+            let serve = self.serve(sink);
+            tokio::pin!(serve);
+            tokio::select! {
+                err = &mut serve => err,
+                () = task => serve.await,
+            }
+        });
+        match result {
+            Ok(err) => RunError::Sink(err),
+            Err(err) => err,
+        }
+    }
+
+    /// Runs [`snapshot`](Self::snapshot) on a new single-threaded runtime.
+    ///
+    /// # Errors
+    /// If the runtime cannot be built or the sink fails.
+    pub fn run_once<S: Sink>(&self, sink: S, delay: Duration) -> Result<(), RunError<S::Error>> {
+        block_on(self.snapshot(sink, delay))?.map_err(RunError::Sink)
+    }
+}
+
+fn block_on<T, E>(future: impl Future<Output = T>) -> Result<T, RunError<E>> {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(RunError::Runtime)?;
+    Ok(rt.block_on(LocalSet::new().run_until(future)))
+}
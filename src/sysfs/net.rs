@@ -0,0 +1,152 @@
+//! Network interfaces from `/sys/class/net`, see `sysfs-class-net` in the
+//! kernel ABI documentation.
+
+use std::path::{Path, PathBuf};
+
+use crate::{read_line::read_line_from_path, CommonError};
+
+pub const DIR: &str = "/sys/class/net";
+
+/// `ARPHRD_LOOPBACK` of `linux/if_arp.h`, the `type` of `lo`.
+pub const ARPHRD_LOOPBACK: u16 = 772;
+
+/// Drivers of phones sharing their connection over USB: RNDIS and CDC for
+/// Android, `ipheth` for iPhones.
+pub const TETHERING_DRIVERS: [&str; 4] = ["rndis_host", "cdc_ether", "cdc_ncm", "ipheth"];
+
+/// Interface names are at most `IFNAMSIZ - 1` bytes.
+pub type Name = heapless::String<15>;
+
+/// RFC 2863 operational state, from `operstate`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OperState {
+    /// What most virtual interfaces without a link state report, e.g. `lo`,
+    /// `tun` and `wg` devices.
+    #[default]
+    Unknown,
+    NotPresent,
+    Down,
+    LowerLayerDown,
+    Testing,
+    Dormant,
+    Up,
+}
+
+impl OperState {
+    fn parse(s: &str) -> Self {
+        match s {
+            "notpresent" => OperState::NotPresent,
+            "down" => OperState::Down,
+            "lowerlayerdown" => OperState::LowerLayerDown,
+            "testing" => OperState::Testing,
+            "dormant" => OperState::Dormant,
+            "up" => OperState::Up,
+            _ => OperState::Unknown,
+        }
+    }
+}
+
+// The flags are independent attributes of sysfs.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Interface {
+    pub name: Name,
+    /// What netlink calls the interface.
+    pub index: u32,
+    pub operstate: OperState,
+    /// Whether the physical link is up. Always `false` while the interface is
+    /// administratively down.
+    pub carrier: bool,
+    /// `ARPHRD_*` hardware type, e.g. 1 for Ethernet and Wi-Fi.
+    pub kind: u16,
+    /// A bridge, whose traffic its ports count too.
+    pub bridge: bool,
+    pub wireless: bool,
+    /// A phone sharing its connection, see [`TETHERING_DRIVERS`].
+    pub tethered: bool,
+}
+
+impl Interface {
+    /// Reads the interface called `name`.
+    ///
+    /// # Errors
+    /// If `operstate` or `type` cannot be read, e.g. when the interface was
+    /// just removed.
+    pub async fn read(name: &str) -> Result<Self, CommonError> {
+        let dir = Path::new(DIR).join(name);
+        let operstate = read_line_from_path::<16>(dir.join("operstate")).await?;
+        let kind = read_line_from_path::<8>(dir.join("type")).await?;
+        let index = read_line_from_path::<12>(dir.join("ifindex")).await?;
+        // Virtual interfaces have no device, let alone a driver.
+        let driver = tokio::fs::read_link(dir.join("device").join("driver")).await.ok();
+        // Reading `carrier` fails with EINVAL while the interface is down.
+        let carrier = read_line_from_path::<4>(dir.join("carrier")).await;
+        Ok(Self {
+            name: Name::try_from(name).map_err(|()| CommonError::Capacity)?,
+            index: index.trim().parse()?,
+            operstate: OperState::parse(operstate.trim()),
+            carrier: carrier.is_ok_and(|carrier| carrier.trim() == "1"),
+            kind: kind.trim().parse()?,
+            bridge: tokio::fs::try_exists(dir.join("bridge")).await?,
+            wireless: tokio::fs::try_exists(dir.join("wireless")).await?
+                || tokio::fs::try_exists(dir.join("phy80211")).await?,
+            tethered: driver.as_deref().and_then(Path::file_name).is_some_and(|driver| {
+                TETHERING_DRIVERS.iter().any(|tethering| driver == *tethering)
+            }),
+        })
+    }
+
+    #[must_use]
+    pub fn is_loopback(&self) -> bool {
+        self.kind == ARPHRD_LOOPBACK
+    }
+
+    /// Whether the interface can pass traffic: `up`, or `unknown` with a
+    /// carrier like tunnels, but never the loopback.
+    #[must_use]
+    pub fn is_up(&self) -> bool {
+        match self.operstate {
+            OperState::Up => true,
+            OperState::Unknown => self.carrier && !self.is_loopback(),
+            _ => false,
+        }
+    }
+
+    fn statistic(&self, name: &str) -> PathBuf {
+        Path::new(DIR).join(self.name.as_str()).join("statistics").join(name)
+    }
+
+    /// Bytes received and sent since the interface was created.
+    ///
+    /// # Errors
+    /// If the counters cannot be read or parsed.
+    pub async fn counters(&self) -> Result<(u64, u64), CommonError> {
+        let rx = read_line_from_path::<24>(self.statistic("rx_bytes")).await?;
+        let tx = read_line_from_path::<24>(self.statistic("tx_bytes")).await?;
+        Ok((rx.trim().parse()?, tx.trim().parse()?))
+    }
+}
+
+/// Every interface in [`DIR`] whose name is `wanted`, read only if it is.
+/// Interfaces removed while listing are skipped.
+///
+/// # Errors
+/// If [`DIR`] cannot be listed.
+pub async fn interfaces(wanted: impl Fn(&str) -> bool) -> Result<Vec<Interface>, CommonError> {
+    let mut interfaces = Vec::new();
+    let mut entries = tokio::fs::read_dir(DIR).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let Some(name) = entry.file_name().to_str().map(str::to_owned) else {
+            continue;
+        };
+        if !wanted(&name) {
+            continue;
+        }
+        match Interface::read(&name).await {
+            Ok(interface) => interfaces.push(interface),
+            Err(err) => log::debug!("Skipping interface {name}: {err}"),
+        }
+    }
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(interfaces)
+}
@@ -0,0 +1,389 @@
+//! User format strings like `"{icon}{percent:>3}%"` over the named fields of
+//! a metric, see [`Metric::fields`].
+
+use std::{
+    fmt::{self, Display, Formatter, Write as _},
+    ops::Range,
+    str::FromStr,
+    time::Duration,
+};
+
+use crate::{
+    combinators::{forward_metric, Either},
+    history::History,
+    Metric,
+};
+
+/// Longest rendered template, in bytes.
+const CAPACITY: usize = 256;
+
+/// Fields of every metric with a [`History`], over the last [`HISTORY_WINDOW`].
+pub const HISTORY_FIELDS: &[&str] = &["trend", "min", "max", "avg"];
+pub const HISTORY_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("Unclosed `{{` at {0}")]
+    Unclosed(usize),
+    #[error("Unmatched `}}` at {0}, write `}}}}` for a literal one")]
+    Unmatched(usize),
+    #[error(
+        "Invalid format spec `{0}`, expected an optional fill character and `<`, `^` or `>`, \
+         and a width"
+    )]
+    Spec(String),
+    #[error("Unknown field `{field}`, expected one of: {expected}")]
+    UnknownField { field: String, expected: String },
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Literal(Range<usize>),
+    Field { name: Range<usize>, fill: char, align: Align, width: usize },
+}
+
+/// A parsed format string. `{{` and `}}` are literal braces, `{name}`,
+/// `{name:>width}` and `{name:fill>width}` are fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    source: String,
+    pieces: Vec<Piece>,
+}
+
+impl FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let mut pieces = Vec::new();
+        let mut literal_start = 0;
+        let mut chars = source.char_indices().peekable();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '{' | '}' if chars.peek().is_some_and(|&(_, next)| next == c) => {
+                    // Keep the first brace of the pair, skip the second.
+                    pieces.push(Piece::Literal(literal_start..i + 1));
+                    chars.next();
+                    literal_start = i + 2;
+                }
+                '}' => return Err(TemplateError::Unmatched(i)),
+                '{' => {
+                    pieces.push(Piece::Literal(literal_start..i));
+                    let end = source[i..].find('}').ok_or(TemplateError::Unclosed(i))? + i;
+                    pieces.push(field(source, i + 1..end)?);
+                    while chars.next_if(|&(j, _)| j <= end).is_some() {}
+                    literal_start = end + 1;
+                }
+                _ => {}
+            }
+        }
+        pieces.push(Piece::Literal(literal_start..source.len()));
+        pieces.retain(|piece| !matches!(piece, Piece::Literal(range) if range.is_empty()));
+
+        Ok(Self { source: source.into(), pieces })
+    }
+}
+
+/// Parses `name` or `name:spec` in `source[range]`.
+fn field(source: &str, range: Range<usize>) -> Result<Piece, TemplateError> {
+    let inner = &source[range.clone()];
+    let (name, spec) = inner.split_once(':').unwrap_or((inner, ""));
+    let name = range.start..range.start + name.len();
+
+    let align_of = |c| match c {
+        '<' => Some(Align::Left),
+        '^' => Some(Align::Center),
+        '>' => Some(Align::Right),
+        _ => None,
+    };
+    let mut chars = spec.chars();
+    let (fill, align, width) = match (chars.next(), chars.next().and_then(align_of)) {
+        (Some(fill), Some(align)) => (fill, align, &spec[fill.len_utf8() + 1..]),
+        (first, _) => match first.and_then(align_of) {
+            Some(align) => (' ', align, &spec[1..]),
+            None => (' ', Align::default(), spec),
+        },
+    };
+    let width = match width {
+        "" => 0,
+        width => width.parse().map_err(|_| TemplateError::Spec(spec.into()))?,
+    };
+
+    Ok(Piece::Field { name, fill, align, width })
+}
+
+impl Template {
+    fn fields(&self) -> impl Iterator<Item = &str> {
+        self.pieces.iter().filter_map(|piece| match piece {
+            Piece::Field { name, .. } => Some(&self.source[name.clone()]),
+            Piece::Literal(_) => None,
+        })
+    }
+
+    /// Checks that every field of the template is one of `fields`.
+    ///
+    /// # Errors
+    /// On the first unknown field.
+    pub fn check(&self, fields: &[&str]) -> Result<(), TemplateError> {
+        match self.fields().find(|field| !fields.contains(field)) {
+            Some(field) => Err(TemplateError::UnknownField {
+                field: field.into(),
+                expected: fields.join(", "),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Renders the template with the fields of `metric`. Nothing is shown if
+    /// any of them has no value.
+    pub fn render<'a, M: Metric>(&'a self, metric: &'a M) -> impl Display + 'a {
+        Rendered { template: self, metric }
+    }
+}
+
+struct Rendered<'a, M> {
+    template: &'a Template,
+    metric: &'a M,
+}
+
+impl<M: Metric> Rendered<'_, M> {
+    /// `None` if a field has no value.
+    fn write(&self, out: &mut heapless::String<CAPACITY>) -> Option<fmt::Result> {
+        let Template { source, pieces } = self.template;
+        for piece in pieces {
+            let result = match piece {
+                Piece::Literal(range) => out.write_str(&source[range.clone()]),
+                Piece::Field { name, fill, align, width } => {
+                    let start = out.len();
+                    let name = &source[name.clone()];
+                    let written = match self.metric.write_field(name, out) {
+                        Some(written) => written,
+                        None => history_field(self.metric.history()?, name, out)?,
+                    };
+                    if let Err(err) = written {
+                        return Some(Err(err));
+                    }
+                    pad(out, start, *fill, *align, *width)
+                }
+            };
+            if result.is_err() {
+                return Some(result);
+            }
+        }
+        Some(Ok(()))
+    }
+}
+
+/// One of [`HISTORY_FIELDS`], `None` while the history is empty.
+fn history_field(history: &History, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+    if name == "trend" {
+        return Some(write!(out, "{}", history.trend(HISTORY_WINDOW)));
+    }
+    let summary = history.summary(HISTORY_WINDOW)?;
+    Some(match name {
+        "min" => write!(out, "{:.0}", summary.min),
+        "max" => write!(out, "{:.0}", summary.max),
+        "avg" => write!(out, "{:.0}", summary.average),
+        _ => return None,
+    })
+}
+
+/// Pads the field written from `start` on to `width` characters with `fill`.
+fn pad(
+    out: &mut heapless::String<CAPACITY>,
+    start: usize,
+    fill: char,
+    align: Align,
+    width: usize,
+) -> fmt::Result {
+    let Some(padding) = width.checked_sub(out[start..].chars().count()) else {
+        return Ok(());
+    };
+    let before = match align {
+        Align::Left => 0,
+        Align::Center => padding / 2,
+        Align::Right => padding,
+    };
+    let repeat = |out: &mut heapless::String<CAPACITY>, count| {
+        (0..count).try_for_each(|_| out.push(fill).map_err(|()| fmt::Error))
+    };
+    if before > 0 {
+        // The field moves right, through a copy as long as the whole text.
+        let value =
+            heapless::String::<CAPACITY>::try_from(&out[start..]).map_err(|()| fmt::Error)?;
+        out.truncate(start);
+        repeat(out, before)?;
+        out.push_str(&value).map_err(|()| fmt::Error)?;
+    }
+    repeat(out, padding - before)
+}
+
+impl<M: Metric> Display for Rendered<'_, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // Rendered into a buffer first, so a field without a value hides the
+        // whole template instead of cutting it short.
+        let mut out = heapless::String::new();
+        match self.write(&mut out) {
+            Some(result) => {
+                result?;
+                f.write_str(&out)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Shows the metric with a [`Template`] instead of its own format, if set.
+#[derive(Debug, Clone)]
+pub struct Formatted<M> {
+    metric: M,
+    template: Option<Template>,
+}
+
+impl<M: Metric> Formatted<M> {
+    /// # Errors
+    /// If the template uses a field the metric does not have.
+    pub fn new(metric: M, template: Option<Template>) -> Result<Self, TemplateError> {
+        if let Some(template) = &template {
+            let history_fields = if metric.history().is_some() { HISTORY_FIELDS } else { &[] };
+            template.check(&[metric.fields(), history_fields].concat())?;
+        }
+        Ok(Self { metric, template })
+    }
+}
+
+impl<M: Metric> Metric for Formatted<M> {
+    forward_metric!(
+        metric: name,
+        update,
+        tooltip,
+        percentage,
+        level,
+        frame_period,
+        animation_frames,
+        history,
+        value,
+        fields,
+        write_field,
+        changed,
+    );
+    fn display(&self) -> impl Display {
+        match &self.template {
+            Some(template) => Either::Left(template.render(&self.metric)),
+            None => Either::Right(self.metric.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, error::Error, future::Future};
+
+    use super::*;
+
+    /// `x` is `value`, `none` never has one.
+    struct Stub {
+        value: u32,
+    }
+
+    impl Metric for Stub {
+        fn name(&self) -> &'static str {
+            "Stub"
+        }
+
+        fn display(&self) -> impl Display {
+            self.value
+        }
+
+        fn update(&self) -> impl Future<Output = Result<(), impl Error>> + '_ {
+            std::future::ready(Ok::<_, Infallible>(()))
+        }
+
+        fn fields(&self) -> &'static [&'static str] {
+            &["x", "none"]
+        }
+
+        fn write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+            match name {
+                "x" => Some(write!(out, "{}", self.value)),
+                _ => None,
+            }
+        }
+    }
+
+    fn render(template: &str, value: u32) -> Result<String, TemplateError> {
+        let (template, stub) = (template.parse::<Template>()?, Stub { value });
+        template.check(stub.fields())?;
+        let rendered = template.render(&stub).to_string();
+        Ok(rendered)
+    }
+
+    #[test]
+    fn literal_braces() -> Result<(), TemplateError> {
+        assert_eq!(render("{{x}} }}{x}{{", 7)?, "{x} }7{");
+        Ok(())
+    }
+
+    #[test]
+    fn unbalanced_braces() {
+        assert_eq!(render("a{x", 7), Err(TemplateError::Unclosed(1)));
+        assert_eq!(render("{x}{", 7), Err(TemplateError::Unclosed(3)));
+        assert_eq!(render("a}b", 7), Err(TemplateError::Unmatched(1)));
+        assert_eq!(render("{x}}", 7), Err(TemplateError::Unmatched(3)));
+    }
+
+    #[test]
+    fn alignment() -> Result<(), TemplateError> {
+        assert_eq!(render("[{x:>3}]", 7)?, "[  7]");
+        assert_eq!(render("[{x:^4}]", 7)?, "[ 7  ]");
+        assert_eq!(render("[{x:<2}]", 7)?, "[7 ]");
+        assert_eq!(render("[{x:2}]", 7)?, "[7 ]");
+        // Never cut short.
+        assert_eq!(render("[{x:>2}]", 1234)?, "[1234]");
+        Ok(())
+    }
+
+    #[test]
+    fn fill() -> Result<(), TemplateError> {
+        assert_eq!(render("[{x: >2}]", 7)?, "[ 7]");
+        assert_eq!(render("[{x:0>3}]", 7)?, "[007]");
+        assert_eq!(render("[{x:·^5}]", 7)?, "[··7··]");
+        assert_eq!(render("[{x:>>3}]", 7)?, "[>>7]");
+        Ok(())
+    }
+
+    #[test]
+    fn bad_spec() {
+        assert_eq!(render("{x:abc}", 7), Err(TemplateError::Spec("abc".into())));
+        assert_eq!(render("{x:>-1}", 7), Err(TemplateError::Spec(">-1".into())));
+        assert_eq!(render("{x:0>3a}", 7), Err(TemplateError::Spec("0>3a".into())));
+    }
+
+    #[test]
+    fn unknown_field() {
+        assert_eq!(
+            render("{x} {y:>2}", 7),
+            Err(TemplateError::UnknownField { field: "y".into(), expected: "x, none".into() })
+        );
+        let template = "{trend}".parse().ok();
+        assert!(matches!(
+            Formatted::new(Stub { value: 7 }, template),
+            Err(TemplateError::UnknownField { .. })
+        ));
+    }
+
+    #[test]
+    fn hidden_without_value() -> Result<(), TemplateError> {
+        assert_eq!(render("a {x} {none:>3} b", 7)?, "");
+        assert_eq!(render("a {x} b", 7)?, "a 7 b");
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+//! Typed values of metrics, see [`Metric::value`](crate::Metric::value), for
+//! consumers that want numbers rather than the displayed text.
+
+use std::fmt::{self, Display, Formatter};
+
+/// Longest [`Value::Text`], in bytes.
+pub const TEXT_CAPACITY: usize = 64;
+
+pub type Text = heapless::String<TEXT_CAPACITY>;
+
+/// What a battery is doing, as reported by the kernel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChargeState {
+    Charging,
+    Discharging,
+    /// Full, or plugged in without charging.
+    Idle,
+}
+
+impl ChargeState {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChargeState::Charging => "charging",
+            ChargeState::Discharging => "discharging",
+            ChargeState::Idle => "idle",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Value {
+    /// In `0..=100`.
+    Percent(u8),
+    /// Bytes per second received and sent.
+    Rate {
+        rx: u64,
+        tx: u64,
+    },
+    Count(usize),
+    /// In kHz.
+    Frequency(u64),
+    /// In millidegrees Celsius.
+    Temperature(i64),
+    /// Load average per core, in hundredths.
+    Load(u32),
+    Text(Text),
+    /// A battery, with its charge in `0..=100`.
+    Charging {
+        percent: u8,
+        state: ChargeState,
+    },
+}
+
+impl Value {
+    /// `text` as a [`Value::Text`], `None` if it is longer than
+    /// [`TEXT_CAPACITY`].
+    #[must_use]
+    pub fn text(text: impl Display) -> Option<Self> {
+        let mut value = Text::new();
+        fmt::Write::write_fmt(&mut value, format_args!("{text}")).ok()?;
+        Some(Value::Text(value))
+    }
+
+    /// The value of [`Value::Percent`], [`Value::Charging`], [`Value::Count`],
+    /// [`Value::Frequency`], [`Value::Temperature`] and [`Value::Load`] as a
+    /// plain number, temperatures in degrees and loads per core.
+    #[must_use]
+    pub fn number(&self) -> Option<f64> {
+        match *self {
+            Value::Percent(percent) | Value::Charging { percent, .. } => Some(f64::from(percent)),
+            Value::Count(count) => Some(count as f64),
+            Value::Frequency(khz) => Some(khz as f64),
+            Value::Temperature(millidegrees) => Some(millidegrees as f64 / 1000.),
+            Value::Load(hundredths) => Some(f64::from(hundredths) / 100.),
+            Value::Rate { .. } | Value::Text(_) => None,
+        }
+    }
+
+    /// The percentage of [`Value::Percent`] and [`Value::Charging`].
+    #[must_use]
+    pub fn percent(&self) -> Option<u8> {
+        match *self {
+            Value::Percent(percent) | Value::Charging { percent, .. } => Some(percent),
+            _ => None,
+        }
+    }
+}
+
+/// Plain text for logs and line-based consumers: `42%`, `rx=1024 tx=0`, `3`,
+/// `2400000 kHz`, `54.5°C`, `0.75`, the text, and `42% charging`.
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Percent(percent) => write!(f, "{percent}%"),
+            Value::Rate { rx, tx } => write!(f, "rx={rx} tx={tx}"),
+            Value::Count(count) => write!(f, "{count}"),
+            Value::Frequency(khz) => write!(f, "{khz} kHz"),
+            Value::Temperature(millidegrees) => write!(f, "{}°C", *millidegrees as f64 / 1000.),
+            Value::Load(hundredths) => write!(f, "{}.{:02}", hundredths / 100, hundredths % 100),
+            Value::Text(text) => f.write_str(text),
+            Value::Charging { percent, state } => write!(f, "{percent}% {}", state.as_str()),
+        }
+    }
+}
@@ -1,4 +1,31 @@
-use std::io::Write;
+use std::{fmt::Write as _, io::Write};
+
+use barstatus::output::{Frame, Sink};
+
+/// Width `dwm` leaves for the status text, in bytes.
+const WIDTH: usize = 93;
+
+/// Sets the root window name, which `dwm` shows as the status text.
+#[derive(Debug, Default)]
+pub struct XSetRootSink;
+
+impl Sink for XSetRootSink {
+    type Error = Error;
+
+    fn write_frame(&mut self, frame: &Frame) -> Result<(), Error> {
+        let mut line = heapless::String::<256>::new();
+        if let Err(err) = write!(line, "{}", frame.joined()) {
+            log::error!("Error while joining metrics: {err}");
+        }
+
+        let mut width = line.len().min(WIDTH);
+        while !line.is_char_boundary(width) {
+            width -= 1;
+        }
+
+        set_on_bar(line[..width].trim_end_matches(frame.separator()))
+    }
+}
 
 #[derive(Debug)]
 pub enum Error {
@@ -20,7 +47,7 @@ pub enum Error {
 pub fn set_on_bar(line: &str) -> Result<(), Error> {
     let mut buf: [u8; 256] = [0; 256];
     let mut writer = std::io::Cursor::new(&mut buf[..]);
-    if let Err(err) = write!(writer, "{line: >93}") {
+    if let Err(err) = write!(writer, "{line: >WIDTH$}") {
         unreachable!("Buffer is big enough for the line: {err}");
     }
     let position = writer.position() as usize + 1;
@@ -39,7 +66,7 @@ pub fn set_on_bar(line: &str) -> Result<(), Error> {
 pub fn set_on_bar(line: &str) -> Result<(), Error> {
     let mut buf: [u8; 256] = [0; 256];
     let mut writer = std::io::Cursor::new(&mut buf[..]);
-    if let Err(err) = write!(writer, "{line: >93}") {
+    if let Err(err) = write!(writer, "{line: >WIDTH$}") {
         unreachable!("Error while writing to alignment buffer: {err}");
     }
     let position = writer.position() as usize + 1;
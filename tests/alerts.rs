@@ -0,0 +1,170 @@
+//! Alert rules against a stand-in notification daemon on a private session
+//! bus. Skipped if `dbus-daemon` is not installed.
+
+// The interface macro passes on the arguments the daemon ignores.
+#![allow(clippy::used_underscore_binding)]
+
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    convert::Infallible,
+    error::Error,
+    fmt::{self, Display},
+    future::Future,
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use barstatus::{
+    alert::{Alerts, Condition, Rule, When},
+    Metric, Value,
+};
+use zbus::zvariant::OwnedValue;
+
+/// A `dbus-daemon --session` of its own, killed on drop.
+struct Bus(Child);
+
+impl Bus {
+    /// Starts the daemon and returns it with its address, `None` without
+    /// `dbus-daemon`.
+    fn start() -> Option<(Self, String)> {
+        let child = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(err) => {
+                eprintln!("Skipping, cannot run dbus-daemon: {err}");
+                return None;
+            }
+        };
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let mut address = String::new();
+        BufReader::new(stdout)
+            .read_line(&mut address)
+            .expect("dbus-daemon prints its address");
+        Some((Self(child), address.trim().to_owned()))
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        _ = self.0.kill();
+        _ = self.0.wait();
+    }
+}
+
+/// Records the summaries of the notifications it is sent.
+struct Daemon(Arc<Mutex<Vec<String>>>);
+
+#[zbus::interface(name = "org.freedesktop.Notifications")]
+impl Daemon {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        _app_name: &str,
+        _replaces_id: u32,
+        _app_icon: &str,
+        summary: &str,
+        _body: &str,
+        _actions: Vec<String>,
+        _hints: HashMap<String, OwnedValue>,
+        _expire_timeout: i32,
+    ) -> u32 {
+        let mut summaries = self.0.lock().expect("not poisoned");
+        summaries.push(summary.to_owned());
+        summaries.len() as u32
+    }
+}
+
+/// A battery whose charge and state the test sets.
+struct Battery {
+    percent: Cell<u8>,
+    state: Cell<&'static str>,
+}
+
+impl Metric for Battery {
+    fn name(&self) -> &'static str {
+        "battery"
+    }
+
+    fn display(&self) -> impl Display {
+        self.percent.get()
+    }
+
+    fn update(&self) -> impl Future<Output = Result<(), impl Error>> + '_ {
+        std::future::ready(Ok::<_, Infallible>(()))
+    }
+
+    fn value(&self) -> Option<Value> {
+        Some(Value::Percent(self.percent.get()))
+    }
+
+    fn fields(&self) -> &'static [&'static str] {
+        &["percent", "state"]
+    }
+
+    fn write_field(&self, name: &str, out: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        Some(match name {
+            "percent" => write!(out, "{}", self.percent.get()),
+            "state" => out.write_str(self.state.get()),
+            _ => return None,
+        })
+    }
+}
+
+#[tokio::test]
+async fn one_notification_per_crossing() {
+    let Some((_bus, address)) = Bus::start() else {
+        return;
+    };
+    // `gdbus` finds the bus through the environment.
+    std::env::set_var("DBUS_SESSION_BUS_ADDRESS", &address);
+
+    let summaries = Arc::new(Mutex::new(Vec::new()));
+    let _daemon = zbus::connection::Builder::address(address.as_str())
+        .expect("valid address")
+        .name("org.freedesktop.Notifications")
+        .expect("valid name")
+        .serve_at("/org/freedesktop/Notifications", Daemon(Arc::clone(&summaries)))
+        .expect("valid path")
+        .build()
+        .await
+        .expect("connected to the private bus");
+
+    let mut alerts = Alerts::default();
+    let when = "state=discharging".parse::<When>().expect("valid predicate");
+    alerts.push("battery", Rule::new(Condition::Below(15.)).when(when));
+    let battery = Battery { percent: Cell::new(50), state: Cell::new("discharging") };
+
+    // Crossing twice, flapping within the hysteresis, plugged in while low,
+    // then re-armed and crossing again.
+    let steps = [
+        (20, "discharging"),
+        (14, "discharging"),
+        (16, "discharging"),
+        (14, "discharging"),
+        (12, "charging"),
+        (14, "discharging"),
+        (21, "charging"),
+        (25, "discharging"),
+        (13, "discharging"),
+    ];
+    tokio::task::LocalSet::new()
+        .run_until(async {
+            for (percent, state) in steps {
+                battery.percent.set(percent);
+                battery.state.set(state);
+                alerts.check(&battery);
+                // Lets the notification go out before the next step.
+                tokio::time::sleep(Duration::from_millis(300)).await;
+            }
+        })
+        .await;
+
+    let summaries = summaries.lock().expect("not poisoned");
+    assert_eq!(*summaries, ["battery below 15 (14%)", "battery below 15 (13%)"]);
+}
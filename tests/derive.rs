@@ -0,0 +1,197 @@
+//! `#[derive(Metric)]` on metrics sampling scripted values.
+
+// The derive awaits `sample`, which has nothing to wait for here.
+#![allow(clippy::unused_async)]
+
+use std::{
+    cell::Cell,
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use barstatus::{
+    history::History,
+    icons::{Icons, Theme},
+    template::HISTORY_WINDOW,
+    Level, Metric, Value,
+};
+
+#[derive(Debug)]
+struct Failed;
+
+impl Display for Failed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("failed")
+    }
+}
+
+impl Error for Failed {}
+
+/// The only field is the state, counting its updates.
+#[derive(Default, Metric)]
+#[metric(sample = sample)]
+struct Counter {
+    count: Cell<Option<u32>>,
+}
+
+impl Counter {
+    async fn sample(&self) -> Result<u32, Failed> {
+        Ok(self.count.get().map_or(1, |count| count + 1))
+    }
+}
+
+/// Samples `next`, failing while it is `None`.
+#[derive(Default, Metric)]
+#[metric(
+    name = "Keyboard",
+    sample = sample,
+    icon = layout,
+    format = "[{icon}|{layout}]",
+    reset_on_error
+)]
+struct Keyboard {
+    #[metric(state)]
+    layout: Cell<Option<&'static str>>,
+    next: Cell<Option<&'static str>>,
+    #[metric(icons)]
+    icons: Icons,
+}
+
+impl Keyboard {
+    async fn sample(&self) -> Result<&'static str, Failed> {
+        self.next.get().ok_or(Failed)
+    }
+}
+
+/// [`Keyboard`] without `reset_on_error`.
+#[derive(Default, Metric)]
+#[metric(sample = sample)]
+struct Sticky {
+    #[metric(state)]
+    layout: Cell<Option<&'static str>>,
+    next: Cell<Option<&'static str>>,
+}
+
+impl Sticky {
+    async fn sample(&self) -> Result<&'static str, Failed> {
+        self.next.get().ok_or(Failed)
+    }
+}
+
+/// Samples `next`, nothing while it is zero.
+#[derive(Default, Metric)]
+#[metric(
+    sample = sample,
+    optional,
+    tooltip = queue_tooltip,
+    level = queue_level,
+    value = queue_value
+)]
+struct Queue {
+    #[metric(state)]
+    length: Cell<Option<usize>>,
+    next: Cell<usize>,
+    #[metric(history)]
+    history: History,
+}
+
+impl Queue {
+    async fn sample(&self) -> Result<Option<usize>, Failed> {
+        Ok(Some(self.next.get()).filter(|&length| length > 0))
+    }
+
+    fn queue_tooltip(&self) -> impl Display {
+        format!("{} queued", self.length.get().unwrap_or_default())
+    }
+
+    fn queue_level(&self) -> Level {
+        if self.length.get().is_some() {
+            Level::Warning
+        } else {
+            Level::Normal
+        }
+    }
+
+    fn queue_value(&self) -> Value {
+        Value::Count(self.length.get().unwrap_or_default())
+    }
+}
+
+fn field(metric: &impl Metric, name: &str) -> Option<String> {
+    let mut out = String::new();
+    metric.write_field(name, &mut out)?.ok()?;
+    Some(out)
+}
+
+#[tokio::test]
+async fn unmarked_state() -> Result<(), Failed> {
+    let counter = Counter::default();
+    assert_eq!(counter.name(), "Counter");
+    assert_eq!(counter.fields(), ["count"]);
+    assert_eq!(counter.display().to_string(), "");
+    assert_eq!(counter.value(), None);
+
+    counter.update().await.map_err(|_| Failed)?;
+    counter.update().await.map_err(|_| Failed)?;
+    assert_eq!(counter.display().to_string(), "2");
+    assert_eq!(field(&counter, "count").as_deref(), Some("2"));
+    assert_eq!(counter.value(), Value::text(2));
+    assert!(counter.history().is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn format_and_icons() -> Result<(), Failed> {
+    let keyboard = Keyboard { icons: Icons::new(Theme::Ascii), ..Keyboard::default() };
+    assert_eq!(keyboard.name(), "Keyboard");
+    assert_eq!(keyboard.fields(), ["icon", "layout"]);
+
+    keyboard.next.set(Some("us"));
+    keyboard.update().await.map_err(|_| Failed)?;
+    assert_eq!(keyboard.display().to_string(), "[KB|us]");
+    assert_eq!(field(&keyboard, "icon").as_deref(), Some("KB"));
+    assert_eq!(field(&keyboard, "layout").as_deref(), Some("us"));
+    assert_eq!(field(&keyboard, "other"), None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn reset_on_error() {
+    let keyboard = Keyboard { next: Cell::new(Some("de")), ..Keyboard::default() };
+    let sticky = Sticky { next: Cell::new(Some("de")), ..Sticky::default() };
+    assert!(keyboard.update().await.is_ok());
+    assert!(sticky.update().await.is_ok());
+
+    keyboard.next.set(None);
+    sticky.next.set(None);
+    assert!(keyboard.update().await.is_err());
+    assert!(sticky.update().await.is_err());
+    assert_eq!(keyboard.display().to_string(), "");
+    assert_eq!(keyboard.value(), None);
+    assert_eq!(field(&keyboard, "layout"), None);
+    assert_eq!(sticky.display().to_string(), "de");
+    assert_eq!(sticky.value(), Value::text("de"));
+}
+
+#[tokio::test]
+async fn optional_with_history() -> Result<(), Failed> {
+    let queue = Queue::default();
+    for next in [3, 5, 0] {
+        queue.next.set(next);
+        queue.update().await.map_err(|_| Failed)?;
+    }
+    // The last sample cleared the state, the value and history go on.
+    assert_eq!(queue.display().to_string(), "");
+    assert_eq!(queue.value(), Some(Value::Count(0)));
+    assert_eq!(queue.level(), Level::Normal);
+    let summary = queue.history().and_then(|history| history.summary(HISTORY_WINDOW));
+    let summary = summary.ok_or(Failed)?;
+    assert_eq!((summary.count, summary.min, summary.max), (3, 0., 5.));
+
+    queue.next.set(4);
+    queue.update().await.map_err(|_| Failed)?;
+    assert_eq!(queue.display().to_string(), "4");
+    assert_eq!(queue.tooltip().to_string(), "4 queued");
+    assert_eq!(queue.level(), Level::Warning);
+    Ok(())
+}